@@ -0,0 +1,255 @@
+//! Per-tick cost of the enemy systems with large hordes, run on a headless `App` so no window or
+//! GPU is needed: `cargo bench`.
+
+use std::{
+    f32::consts::FRAC_PI_6,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, Bencher, BenchmarkId, Criterion};
+use rand::{prelude::StdRng, Rng, SeedableRng};
+
+use bevy_twinstick::{
+    actions::Action,
+    ai::AiState,
+    app_state::AppState,
+    arena::{ArenaPlugin, ARENA_HALF_EXTENTS},
+    components::{
+        ContactCooldown, CurrentAmmo, Enemy, FireDelayTimer, Health, InvincibilityTimer,
+        Invincible, KnockbackVelocity, MaximumAmmo, Player, Reloading, Velocity,
+    },
+    enemies::{enemy_collisions, enemy_damage, enemy_movement, spawn_enemy},
+    enemy_kinds::EnemyKinds,
+    flow_field::FlowField,
+    gamepad::AimDirection,
+    player::spawn_player,
+    spatial_grid::{update_spatial_grid, SpatialGridPlugin},
+    weapons::{shoot_weapon, WeaponPlugin},
+};
+
+// Constants
+const ENEMY_COUNTS: [usize; 3] = [1_000, 10_000, 50_000];
+const ENEMY_KIND: &str = "Grunt";
+// Enemies shrug off every shot, so each tick hits the same amount of them
+const ENEMY_HEALTH: i32 = i32::MAX;
+// Well inside the weapon's range of 500
+const CONE_LENGTH: f32 = 450.0;
+
+/// Enemies to spawn, as seen from the player at the origin.
+enum Formation {
+    /// Spread over the whole arena
+    Arena,
+    /// Packed into the cone the player shoots into
+    Cone,
+}
+
+impl Formation {
+    fn position(&self, rng: &mut StdRng) -> Vec3 {
+        match self {
+            Formation::Arena => Vec3::new(
+                rng.gen_range(-ARENA_HALF_EXTENTS.x..ARENA_HALF_EXTENTS.x),
+                rng.gen_range(-ARENA_HALF_EXTENTS.y..ARENA_HALF_EXTENTS.y),
+                0.0,
+            ),
+            // Shots spread 30 degrees to either side of the aim, which points along +x
+            Formation::Cone => {
+                let angle = rng.gen_range(-FRAC_PI_6..FRAC_PI_6) * 0.9;
+                let distance = rng.gen_range(30.0..CONE_LENGTH);
+
+                Vec3::new(angle.cos() * distance, angle.sin() * distance, 0.0)
+            }
+        }
+    }
+}
+
+/// Headless app with the player at the origin and `count` chasing enemies; call `update` once
+/// the systems under test are added to run the startup systems.
+fn horde_app(count: usize, formation: Formation) -> App {
+    let enemy_kinds = EnemyKinds::bundled();
+    let enemy_kind = enemy_kinds
+        .iter()
+        .find(|kind| kind.name == ENEMY_KIND)
+        .unwrap()
+        .clone();
+
+    let mut flow_field = FlowField::new(ARENA_HALF_EXTENTS);
+    flow_field.recompute(Vec2::ZERO);
+
+    let mut app = App::new();
+
+    app.add_plugins(MinimalPlugins)
+        .add_state(AppState::Playing)
+        .add_plugin(SpatialGridPlugin)
+        .insert_resource(flow_field)
+        .add_startup_system(spawn_player)
+        .add_startup_system(move |mut commands: Commands| {
+            let mut rng = StdRng::seed_from_u64(count as u64);
+
+            for _ in 0..count {
+                spawn_enemy(
+                    &mut commands,
+                    &enemy_kind,
+                    formation.position(&mut rng),
+                    AiState::Chase,
+                );
+            }
+        });
+
+    app
+}
+
+/// Lets every enemy survive whatever hits it.
+fn toughen(app: &mut App) {
+    let mut enemy_query = app.world.query_filtered::<&mut Health, With<Enemy>>();
+
+    for mut health in enemy_query.iter_mut(&mut app.world) {
+        **health = ENEMY_HEALTH;
+    }
+}
+
+/// Times one tick per iteration, each starting from the horde as it was spawned. Otherwise chasing
+/// enemies pile up on the player over the run and every tick costs more than the one before.
+fn bench_ticks(b: &mut Bencher, app: &mut App) {
+    let formation: Vec<(Entity, Transform)> = app
+        .world
+        .query_filtered::<(Entity, &Transform), With<Enemy>>()
+        .iter(&app.world)
+        .map(|(enemy_ent, enemy_transform)| (enemy_ent, *enemy_transform))
+        .collect();
+
+    b.iter_custom(|iterations| {
+        let mut elapsed = Duration::ZERO;
+
+        for _ in 0..iterations {
+            reset(app, &formation);
+
+            let start = Instant::now();
+            app.update();
+            elapsed += start.elapsed();
+        }
+
+        elapsed
+    });
+}
+
+/// Puts every enemy back where it spawned, at rest and ready to attack, and lets the player be hurt
+/// again.
+fn reset(app: &mut App, formation: &[(Entity, Transform)]) {
+    let mut enemy_query = app.world.query::<(
+        &mut Transform,
+        &mut Velocity,
+        &mut KnockbackVelocity,
+        &mut ContactCooldown,
+    )>();
+
+    for (enemy_ent, spawn_transform) in formation {
+        if let Ok((mut transform, mut velocity, mut knockback_velocity, mut contact_cooldown)) =
+            enemy_query.get_mut(&mut app.world, *enemy_ent)
+        {
+            *transform = *spawn_transform;
+            **velocity = Vec2::ZERO;
+            **knockback_velocity = Vec2::ZERO;
+            let duration = contact_cooldown.duration();
+            contact_cooldown.tick(duration);
+        }
+    }
+
+    let mut player_query = app
+        .world
+        .query_filtered::<(&mut Invincible, &mut InvincibilityTimer), With<Player>>();
+
+    for (mut invincible, mut invincibility_timer) in player_query.iter_mut(&mut app.world) {
+        **invincible = false;
+        invincibility_timer.reset();
+    }
+}
+
+/// Keeps every weapon loaded and ready, so the held trigger fires on each tick.
+fn rearm(
+    mut weapon_query: Query<(
+        &MaximumAmmo,
+        &mut CurrentAmmo,
+        &mut FireDelayTimer,
+        &mut Reloading,
+    )>,
+) {
+    for (maximum_ammo, mut current_ammo, mut fire_delay_timer, mut reloading) in
+        weapon_query.iter_mut()
+    {
+        **current_ammo = **maximum_ammo;
+        let duration = fire_delay_timer.duration();
+        fire_delay_timer.tick(duration);
+        **reloading = false;
+    }
+}
+
+fn movement(c: &mut Criterion) {
+    let mut group = c.benchmark_group("enemy_movement");
+    group.sample_size(10);
+
+    for count in ENEMY_COUNTS {
+        // Collisions with each other and the obstacles are part of every movement tick
+        let mut app = horde_app(count, Formation::Arena);
+        app.add_plugin(ArenaPlugin)
+            .add_system(enemy_movement.after(update_spatial_grid))
+            .add_system(enemy_collisions.after(enemy_movement));
+        app.update();
+        toughen(&mut app);
+
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, _| {
+            bench_ticks(b, &mut app)
+        });
+    }
+
+    group.finish();
+}
+
+fn contact_damage(c: &mut Criterion) {
+    let mut group = c.benchmark_group("enemy_damage");
+    group.sample_size(10);
+
+    for count in ENEMY_COUNTS {
+        let mut app = horde_app(count, Formation::Arena);
+        app.add_system(enemy_damage.after(update_spatial_grid));
+        app.update();
+        toughen(&mut app);
+
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, _| {
+            bench_ticks(b, &mut app)
+        });
+    }
+
+    group.finish();
+}
+
+fn full_cone_shot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("shoot_weapon");
+    group.sample_size(10);
+
+    for count in ENEMY_COUNTS {
+        // The first weapon stays selected and the trigger is held down for good, since nothing
+        // clears the actions
+        let mut actions = Input::<Action>::default();
+        actions.press(Action::SelectWeapon(1));
+        actions.press(Action::Fire);
+
+        let mut app = horde_app(count, Formation::Cone);
+        app.add_plugin(WeaponPlugin)
+            .insert_resource(actions)
+            .init_resource::<AimDirection>()
+            .add_system(rearm.before(shoot_weapon));
+        app.update();
+        toughen(&mut app);
+        **app.world.resource_mut::<AimDirection>() = Vec2::X;
+
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, _| {
+            b.iter(|| app.update())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, movement, contact_damage, full_cone_shot);
+criterion_main!(benches);
@@ -0,0 +1,267 @@
+use std::{collections::BTreeMap, fs, marker::PhantomData, path::Path};
+
+use anyhow::Context;
+use bevy::{
+    asset::FileAssetIo,
+    ecs::system::SystemParam,
+    input::{gamepad::GamepadButtonType, InputSystem},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+// Constants
+// Relative to the directory assets are loaded from, not wherever the game was started
+const BINDINGS_PATH: &str = "config/input.ron";
+
+// Actions
+/// Everything the player can do, independent of what it is bound to. Gameplay reads these through
+/// `Res<Input<Action>>` instead of looking at keys and buttons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    /// Fires the secondary weapon, i.e. the one selected before the current one
+    SecondaryFire,
+    Reload,
+    Roll,
+    /// Counted from one, like the number keys it is bound to by default
+    SelectWeapon(usize),
+    NextWeapon,
+    PreviousWeapon,
+    Pause,
+    /// Starts and restarts runs
+    Confirm,
+    /// Leaves the game-over screen for the main menu
+    Back,
+}
+
+/// A physical input that can trigger an action. Gamepad buttons count on any connected gamepad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+// Resources
+/// Which inputs trigger which action, stored in `config/input.ron` next to the `assets` folder.
+#[derive(Clone, Debug, Deref, DerefMut, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InputBindings(BTreeMap<Action, Vec<Binding>>);
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use Binding::*;
+
+        let mut bindings = BTreeMap::from([
+            (Action::MoveUp, vec![Key(KeyCode::W)]),
+            (Action::MoveDown, vec![Key(KeyCode::S)]),
+            (Action::MoveLeft, vec![Key(KeyCode::A)]),
+            (Action::MoveRight, vec![Key(KeyCode::D)]),
+            (
+                Action::Fire,
+                vec![
+                    Mouse(MouseButton::Left),
+                    Gamepad(GamepadButtonType::RightTrigger2),
+                ],
+            ),
+            (
+                Action::SecondaryFire,
+                vec![
+                    Mouse(MouseButton::Right),
+                    Gamepad(GamepadButtonType::LeftTrigger2),
+                ],
+            ),
+            (
+                Action::Reload,
+                vec![Key(KeyCode::R), Gamepad(GamepadButtonType::West)],
+            ),
+            (
+                Action::Roll,
+                vec![Key(KeyCode::Space), Gamepad(GamepadButtonType::South)],
+            ),
+            (
+                Action::NextWeapon,
+                vec![Gamepad(GamepadButtonType::RightTrigger)],
+            ),
+            (
+                Action::PreviousWeapon,
+                vec![Gamepad(GamepadButtonType::LeftTrigger)],
+            ),
+            (
+                Action::Pause,
+                vec![Key(KeyCode::Escape), Gamepad(GamepadButtonType::Start)],
+            ),
+            (
+                Action::Confirm,
+                vec![Key(KeyCode::Return), Gamepad(GamepadButtonType::South)],
+            ),
+            (
+                Action::Back,
+                vec![Key(KeyCode::Escape), Gamepad(GamepadButtonType::East)],
+            ),
+        ]);
+
+        let number_keys = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+        ];
+        for (index, key) in number_keys.into_iter().enumerate() {
+            bindings.insert(Action::SelectWeapon(index + 1), vec![Key(key)]);
+        }
+
+        InputBindings(bindings)
+    }
+}
+
+impl InputBindings {
+    /// Reads the bindings at `path`.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read input bindings from {}", path.display()))?;
+
+        InputBindings::parse(&contents)
+            .with_context(|| format!("Failed to parse input bindings in {}", path.display()))
+    }
+
+    /// Reads bindings from RON. Actions missing from it keep their default bindings, so older
+    /// files stay usable when actions are added.
+    pub fn parse(contents: &str) -> Result<Self, ron::Error> {
+        let mut bindings: InputBindings = ron::de::from_str(contents)?;
+
+        for (action, default_bindings) in InputBindings::default().0 {
+            bindings.entry(action).or_insert(default_bindings);
+        }
+
+        Ok(bindings)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::write(path, contents)
+            .with_context(|| format!("Failed to write input bindings to {}", path.display()))
+    }
+
+    /// The bindings from the config file, which is created with the defaults on first launch. A
+    /// broken file is left alone for the player to fix and the defaults are used meanwhile.
+    fn load_or_create(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            let bindings = InputBindings::default();
+
+            if let Err(error) = bindings.save(path) {
+                warn!("{:?}", error);
+            }
+
+            return bindings;
+        }
+
+        InputBindings::load(path).unwrap_or_else(|error| {
+            warn!("{:?}", error);
+            InputBindings::default()
+        })
+    }
+}
+
+// System params
+/// The raw inputs bindings can refer to.
+#[derive(SystemParam)]
+struct BoundInputs<'w, 's> {
+    keys: Res<'w, Input<KeyCode>>,
+    mouse_buttons: Res<'w, Input<MouseButton>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, Input<GamepadButton>>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> BoundInputs<'w, 's> {
+    fn pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.pressed(key),
+            Binding::Mouse(button) => self.mouse_buttons.pressed(button),
+            Binding::Gamepad(button_type) => self.gamepads.iter().any(|gamepad| {
+                self.gamepad_buttons
+                    .pressed(GamepadButton(*gamepad, button_type))
+            }),
+        }
+    }
+}
+
+// Plugin
+pub struct ActionPlugin;
+
+impl Plugin for ActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PreStartup, load_bindings)
+            .add_system_to_stage(CoreStage::PreUpdate, update_actions.after(InputSystem))
+            .init_resource::<Input<Action>>();
+    }
+}
+
+// Systems
+fn load_bindings(mut commands: Commands) {
+    let path = FileAssetIo::get_root_path().join(BINDINGS_PATH);
+
+    commands.insert_resource(InputBindings::load_or_create(path));
+}
+
+/// Presses every action with at least one of its bindings held and releases the others.
+fn update_actions(
+    bound_inputs: BoundInputs,
+    bindings: Res<InputBindings>,
+    mut actions: ResMut<Input<Action>>,
+) {
+    actions.clear();
+
+    for (action, action_bindings) in bindings.iter() {
+        let pressed = action_bindings
+            .iter()
+            .any(|binding| bound_inputs.pressed(*binding));
+
+        if pressed && !actions.pressed(*action) {
+            actions.press(*action);
+        } else if !pressed && actions.pressed(*action) {
+            actions.release(*action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings_round_trip() {
+        let bindings = InputBindings::default();
+        let contents =
+            ron::ser::to_string_pretty(&bindings, ron::ser::PrettyConfig::default()).unwrap();
+        let parsed: InputBindings = ron::de::from_str(&contents).unwrap();
+
+        assert_eq!(*parsed, *bindings);
+    }
+
+    #[test]
+    fn missing_actions_fall_back_to_defaults() {
+        let bindings = InputBindings::parse("{ Fire: [Key(F)] }").unwrap();
+
+        assert_eq!(bindings[&Action::Fire], vec![Binding::Key(KeyCode::F)]);
+        assert_eq!(
+            bindings[&Action::Reload],
+            InputBindings::default()[&Action::Reload]
+        );
+    }
+}
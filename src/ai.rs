@@ -0,0 +1,437 @@
+use std::f32::consts::PI;
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use rand::random;
+use serde::Deserialize;
+
+use crate::{
+    app_state::AppState,
+    components::{Boss, Enemy, EnemyBehaviour, Health, MaxHealth, Player, Stunned},
+    enemies::{enemy_movement, EnemyKilled},
+    flow_field::FlowField,
+    spawning::LEASH_DISTANCE,
+    util::VectorMath,
+};
+
+// Constants
+const IDLE_TIME_MIN: f32 = 1.0;
+const IDLE_TIME_MAX: f32 = 3.0;
+const WANDER_TIME: f32 = 2.5;
+const WANDER_RADIUS: f32 = 150.0;
+const WANDER_ARRIVAL_DISTANCE: f32 = 10.0;
+const WANDER_SPEED_FACTOR: f32 = 0.4;
+
+// Attacking enemies only fall back to chasing once the player is this much beyond their attack
+// range, so they do not flicker between the two states at the border
+const ATTACK_RANGE_HYSTERESIS: f32 = 1.2;
+const RANGED_DISTANCE_TOLERANCE: f32 = 40.0;
+// Deaths within this distance alert idle enemies
+const ALERT_RADIUS: f32 = 300.0;
+
+// States
+#[derive(Clone, Copy, Component, Debug, PartialEq, Eq)]
+pub enum AiState {
+    /// Standing around until the player comes close
+    Idle,
+    /// Strolling to a random spot nearby
+    Wander,
+    /// Closing in on the player along the flow field
+    Chase,
+    /// Close enough to fight, in whatever way the enemy's behaviour does that
+    Attack,
+    /// Running away from the player
+    Flee,
+    /// Knocked back by a hit and not steering
+    Stunned,
+}
+
+/// Per-kind table of the distances and thresholds driving the state machine.
+#[derive(Clone, Component, Deserialize)]
+#[serde(default)]
+pub struct AiProfile {
+    /// Idle and wandering enemies start chasing once the player is this close
+    pub aggro_radius: f32,
+    /// Chasing enemies give up once the player is this far away
+    pub leash_radius: f32,
+    pub attack_range: f32,
+    /// Health fraction under which the enemy flees, zero for enemies that never do
+    pub flee_below: f32,
+    /// Whether idle enemies move around or stand still
+    pub wanders: bool,
+}
+
+impl Default for AiProfile {
+    fn default() -> Self {
+        Self {
+            aggro_radius: 700.0,
+            leash_radius: 1100.0,
+            attack_range: 50.0,
+            flee_below: 0.0,
+            wanders: true,
+        }
+    }
+}
+
+impl AiProfile {
+    /// The profile for enemies sent after the player. They keep chasing until `leash_enemies`
+    /// recycles them, rather than giving up out of reach while still holding up the wave.
+    fn hunting(&self) -> Self {
+        Self {
+            leash_radius: self.leash_radius.max(LEASH_DISTANCE),
+            ..self.clone()
+        }
+    }
+}
+
+/// What an enemy knows about the world when deciding on its next state.
+pub struct Senses {
+    pub distance_to_player: f32,
+    pub health_fraction: f32,
+    pub stunned: bool,
+    /// Hurt or alerted since the last update
+    pub provoked: bool,
+    /// The idle or wander timer ran out
+    pub bored: bool,
+}
+
+#[derive(Component, Deref, DerefMut)]
+pub struct AiTimer(Timer);
+
+#[derive(Component, Deref, DerefMut)]
+pub struct WanderTarget(Vec2);
+
+/// Health as of the last update, so only drops count as getting hurt and regeneration does not.
+/// Empty until the first update, as elites only get their health after the AI is set up
+#[derive(Component, Deref, DerefMut)]
+pub struct PreviousHealth(Option<i32>);
+
+impl AiState {
+    pub fn next(self, profile: &AiProfile, senses: &Senses) -> AiState {
+        if senses.stunned {
+            return AiState::Stunned;
+        }
+
+        if senses.health_fraction < profile.flee_below {
+            return if senses.distance_to_player > profile.leash_radius {
+                AiState::Idle
+            } else {
+                AiState::Flee
+            };
+        }
+
+        match self {
+            AiState::Idle | AiState::Wander
+                if senses.provoked || senses.distance_to_player <= profile.aggro_radius =>
+            {
+                AiState::Chase
+            }
+            AiState::Idle if senses.bored && profile.wanders => AiState::Wander,
+            AiState::Wander if senses.bored => AiState::Idle,
+            AiState::Idle | AiState::Wander => self,
+            AiState::Chase if senses.distance_to_player > profile.leash_radius => AiState::Idle,
+            AiState::Chase if senses.distance_to_player <= profile.attack_range => AiState::Attack,
+            AiState::Attack
+                if senses.distance_to_player > profile.attack_range * ATTACK_RANGE_HYSTERESIS =>
+            {
+                AiState::Chase
+            }
+            AiState::Chase | AiState::Attack => self,
+            // Recovered enough health or got hit, either way it is time to fight again
+            AiState::Flee | AiState::Stunned => AiState::Chase,
+        }
+    }
+
+    /// Direction the enemy wants to move in and the fraction of its speed to do it with.
+    pub fn steering(
+        self,
+        behaviour: &EnemyBehaviour,
+        enemy_position: Vec2,
+        player_position: Vec2,
+        wander_target: Vec2,
+        flow_field: &FlowField,
+    ) -> (Vec2, f32) {
+        match self {
+            AiState::Idle | AiState::Stunned => (Vec2::ZERO, 1.0),
+            AiState::Wander => {
+                if enemy_position.distance(wander_target) <= WANDER_ARRIVAL_DISTANCE {
+                    (Vec2::ZERO, WANDER_SPEED_FACTOR)
+                } else {
+                    (
+                        enemy_position.scaled_vector_to(&wander_target, 1.0),
+                        WANDER_SPEED_FACTOR,
+                    )
+                }
+            }
+            // Follow the flow field around obstacles, and walk straight at the player once there
+            // is nothing left to route around
+            AiState::Chase => (
+                flow_field
+                    .direction_at(enemy_position)
+                    .unwrap_or_else(|| enemy_position.scaled_vector_to(&player_position, 1.0)),
+                1.0,
+            ),
+            AiState::Attack => match behaviour {
+                // Ranged enemies hold their ground around their preferred distance and back off
+                // when the player gets too close
+                EnemyBehaviour::Ranged {
+                    preferred_distance, ..
+                } => {
+                    let distance = enemy_position.distance(player_position);
+
+                    if distance < preferred_distance - RANGED_DISTANCE_TOLERANCE {
+                        (player_position.scaled_vector_to(&enemy_position, 1.0), 1.0)
+                    } else if distance <= preferred_distance + RANGED_DISTANCE_TOLERANCE {
+                        (Vec2::ZERO, 1.0)
+                    } else {
+                        (enemy_position.scaled_vector_to(&player_position, 1.0), 1.0)
+                    }
+                }
+                EnemyBehaviour::Chase | EnemyBehaviour::Explode { .. } => {
+                    (enemy_position.scaled_vector_to(&player_position, 1.0), 1.0)
+                }
+            },
+            AiState::Flee => (player_position.scaled_vector_to(&enemy_position, 1.0), 1.0),
+        }
+    }
+}
+
+// Plugin
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(update_ai_states.before(enemy_movement)),
+        );
+    }
+}
+
+/// Gives a freshly spawned enemy its state machine, starting out in `state` at `position`. Enemies
+/// sent after the player start out chasing, as they appear well beyond most aggro radii; only
+/// enemies placed in the level wait idle for the player to come by.
+pub fn insert_ai(enemy: &mut EntityCommands, profile: &AiProfile, position: Vec2, state: AiState) {
+    let profile = if state == AiState::Chase {
+        profile.hunting()
+    } else {
+        profile.clone()
+    };
+
+    enemy
+        .insert(state)
+        .insert(profile)
+        .insert(AiTimer(Timer::from_seconds(idle_time(), false)))
+        .insert(WanderTarget(position))
+        .insert(PreviousHealth(None));
+}
+
+fn idle_time() -> f32 {
+    IDLE_TIME_MIN + random::<f32>() * (IDLE_TIME_MAX - IDLE_TIME_MIN)
+}
+
+// Systems
+fn update_ai_states(
+    time: Res<Time>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+    player_transform: Query<&Transform, With<Player>>,
+    mut enemy_query: Query<
+        (
+            &Transform,
+            &Health,
+            &mut PreviousHealth,
+            &MaxHealth,
+            &Stunned,
+            &AiProfile,
+            &mut AiState,
+            &mut AiTimer,
+            &mut WanderTarget,
+        ),
+        (With<Enemy>, Without<Boss>, Without<Player>),
+    >,
+) {
+    let player_position = player_transform.single().translation.truncate();
+    let deaths: Vec<Vec2> = enemy_killed
+        .iter()
+        .map(|enemy_killed| enemy_killed.position)
+        .collect();
+
+    for (
+        enemy_transform,
+        health,
+        mut previous_health,
+        max_health,
+        stunned,
+        profile,
+        mut ai_state,
+        mut ai_timer,
+        mut wander_target,
+    ) in enemy_query.iter_mut()
+    {
+        ai_timer.tick(time.delta());
+
+        let enemy_position = enemy_transform.translation.truncate();
+        let hurt = matches!(**previous_health, Some(previous) if **health < previous);
+        **previous_health = Some(**health);
+        let alerted = deaths
+            .iter()
+            .any(|position| position.distance(enemy_position) <= ALERT_RADIUS);
+
+        let senses = Senses {
+            distance_to_player: enemy_position.distance(player_position),
+            health_fraction: **health as f32 / **max_health as f32,
+            stunned: **stunned,
+            provoked: hurt || alerted,
+            bored: ai_timer.finished(),
+        };
+        let next_state = ai_state.next(profile, &senses);
+
+        if next_state == *ai_state {
+            continue;
+        }
+
+        match next_state {
+            AiState::Idle => **ai_timer = Timer::from_seconds(idle_time(), false),
+            AiState::Wander => {
+                let angle = random::<f32>() * 2.0 * PI;
+
+                **wander_target =
+                    enemy_position + Vec2::X.rotated_by(angle) * random::<f32>() * WANDER_RADIUS;
+                **ai_timer = Timer::from_seconds(WANDER_TIME, false);
+            }
+            _ => {}
+        }
+
+        *ai_state = next_state;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        arena::ARENA_HALF_EXTENTS, enemies::spawn_enemy, enemy_kinds::EnemyKinds,
+        player::spawn_player,
+    };
+
+    fn senses(distance_to_player: f32) -> Senses {
+        Senses {
+            distance_to_player,
+            health_fraction: 1.0,
+            stunned: false,
+            provoked: false,
+            bored: false,
+        }
+    }
+
+    #[test]
+    fn aggro_attack_and_leash() {
+        let profile = AiProfile::default();
+
+        assert_eq!(AiState::Idle.next(&profile, &senses(1000.0)), AiState::Idle);
+        assert_eq!(AiState::Idle.next(&profile, &senses(600.0)), AiState::Chase);
+        assert_eq!(
+            AiState::Chase.next(&profile, &senses(40.0)),
+            AiState::Attack
+        );
+        assert_eq!(
+            AiState::Attack.next(&profile, &senses(55.0)),
+            AiState::Attack
+        );
+        assert_eq!(
+            AiState::Attack.next(&profile, &senses(80.0)),
+            AiState::Chase
+        );
+        assert_eq!(
+            AiState::Chase.next(&profile, &senses(1200.0)),
+            AiState::Idle
+        );
+    }
+
+    #[test]
+    fn events_and_health_override_distance() {
+        let profile = AiProfile {
+            flee_below: 0.25,
+            ..default()
+        };
+
+        let provoked = Senses {
+            provoked: true,
+            ..senses(1000.0)
+        };
+        assert_eq!(AiState::Wander.next(&profile, &provoked), AiState::Chase);
+
+        let stunned = Senses {
+            stunned: true,
+            ..senses(10.0)
+        };
+        assert_eq!(AiState::Attack.next(&profile, &stunned), AiState::Stunned);
+        assert_eq!(
+            AiState::Stunned.next(&profile, &senses(10.0)),
+            AiState::Chase
+        );
+
+        let hurt = Senses {
+            health_fraction: 0.2,
+            ..senses(10.0)
+        };
+        assert_eq!(AiState::Attack.next(&profile, &hurt), AiState::Flee);
+    }
+
+    #[test]
+    fn hunting_enemies_chase_until_recycled() {
+        let profile = AiProfile::default();
+
+        assert_eq!(
+            AiState::Chase.next(&profile, &senses(LEASH_DISTANCE)),
+            AiState::Idle
+        );
+        assert_eq!(
+            AiState::Chase.next(&profile.hunting(), &senses(LEASH_DISTANCE)),
+            AiState::Chase
+        );
+    }
+
+    #[test]
+    fn wave_enemies_close_in_from_beyond_aggro_radius() {
+        let grunt = EnemyKinds::bundled()
+            .iter()
+            .find(|kind| kind.name == "Grunt")
+            .unwrap()
+            .clone();
+        let spawn_position = Vec2::new(grunt.ai.aggro_radius + 200.0, 0.0);
+
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins)
+            .add_event::<EnemyKilled>()
+            .add_startup_system(spawn_player)
+            .add_startup_system(move |mut commands: Commands| {
+                spawn_enemy(
+                    &mut commands,
+                    &grunt,
+                    spawn_position.extend(0.0),
+                    AiState::Chase,
+                );
+            })
+            .add_system(update_ai_states);
+        app.update();
+
+        let (ai_state, behaviour, wander_target) = app
+            .world
+            .query::<(&AiState, &EnemyBehaviour, &WanderTarget)>()
+            .iter(&app.world)
+            .next()
+            .unwrap();
+        let (direction, speed_factor) = ai_state.steering(
+            behaviour,
+            spawn_position,
+            Vec2::ZERO,
+            **wander_target,
+            &FlowField::new(ARENA_HALF_EXTENTS),
+        );
+
+        assert_eq!(*ai_state, AiState::Chase);
+        assert!(direction.dot(-spawn_position) > 0.0);
+        assert!(speed_factor > 0.0);
+    }
+}
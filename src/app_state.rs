@@ -0,0 +1,71 @@
+use bevy::{asset::LoadState, prelude::*};
+
+use crate::enemy_kinds::EnemyKindsHandle;
+
+// Constants
+const FONT_PATH: &str = "fonts/FiraSans-Bold.ttf";
+
+// States
+/// Top-level flow of the game. Gameplay systems only run while `Playing`, so everything they
+/// drive, timers and spawning included, stands still in every other state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AppState {
+    /// Waiting for the assets the game cannot start without
+    Loading,
+    MainMenu,
+    /// A run is in progress. Runs are set up when entering this state and torn down when leaving
+    /// `GameOver`
+    Playing,
+    /// Pushed on top of `Playing`, so popping it resumes the run as it was
+    Paused,
+    /// The player died; the run stays on screen until it is restarted or abandoned
+    GameOver,
+}
+
+// Resources
+/// Handles kept alive during loading, so the menus do not show up before their font.
+#[derive(Default, Deref, DerefMut)]
+struct LoadingAssets(Vec<HandleUntyped>);
+
+// Plugin
+pub struct AppStatePlugin;
+
+impl Plugin for AppStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state(AppState::Loading)
+            .add_startup_system(start_loading)
+            .add_system_set(SystemSet::on_update(AppState::Loading).with_system(finish_loading))
+            .init_resource::<LoadingAssets>();
+    }
+}
+
+/// Removes every entity with a `T`, for tearing down whatever a state set up.
+pub fn despawn_all<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Systems
+fn start_loading(asset_server: Res<AssetServer>, mut loading_assets: ResMut<LoadingAssets>) {
+    loading_assets.push(asset_server.load_untyped(FONT_PATH));
+}
+
+fn finish_loading(
+    asset_server: Res<AssetServer>,
+    mut app_state: ResMut<State<AppState>>,
+    loading_assets: Res<LoadingAssets>,
+    enemy_kinds_handle: Res<EnemyKindsHandle>,
+) {
+    let handles = loading_assets
+        .iter()
+        .map(|handle| handle.id)
+        .chain([enemy_kinds_handle.id]);
+
+    match asset_server.get_group_load_state(handles) {
+        LoadState::Loaded => app_state.set(AppState::MainMenu).unwrap(),
+        // Nothing works without these, so there is no point in carrying on
+        LoadState::Failed => panic!("Failed to load game assets"),
+        _ => {}
+    }
+}
@@ -0,0 +1,114 @@
+use bevy::{math::const_vec2, prelude::*};
+
+use crate::{
+    app_state::AppState,
+    components::{Enemy, HitBox, Obstacle, Player, SpawnPoint},
+    enemies::enemy_collisions,
+    player::player_movement,
+};
+
+// Constants
+pub const ARENA_HALF_EXTENTS: Vec2 = const_vec2!([1500.0, 1500.0]);
+const WALL_THICKNESS: f32 = 40.0;
+const OBSTACLE_COLOR: Color = Color::GRAY;
+
+// (x, y, width, height)
+const OBSTACLES: [(f32, f32, f32, f32); 6] = [
+    (-400.0, 300.0, 400.0, 40.0),
+    (400.0, -300.0, 400.0, 40.0),
+    (-300.0, -400.0, 40.0, 300.0),
+    (300.0, 400.0, 40.0, 300.0),
+    (-900.0, 0.0, 40.0, 800.0),
+    (900.0, 0.0, 40.0, 800.0),
+];
+
+// (x, y)
+const SPAWN_POINTS: [(f32, f32); 8] = [
+    (-1300.0, -1300.0),
+    (-1300.0, 1300.0),
+    (1300.0, -1300.0),
+    (1300.0, 1300.0),
+    (0.0, 1300.0),
+    (0.0, -1300.0),
+    (-1300.0, 0.0),
+    (1300.0, 0.0),
+];
+
+// Plugin
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_arena).add_system_set(
+            SystemSet::on_update(AppState::Playing).with_system(
+                obstacle_collisions
+                    .after(player_movement)
+                    .after(enemy_collisions),
+            ),
+        );
+    }
+}
+
+// Systems
+fn spawn_arena(mut commands: Commands) {
+    let wall_length = ARENA_HALF_EXTENTS * 2.0 + WALL_THICKNESS * 2.0;
+    let wall_offset = ARENA_HALF_EXTENTS + WALL_THICKNESS / 2.0;
+
+    let walls = [
+        (0.0, wall_offset.y, wall_length.x, WALL_THICKNESS),
+        (0.0, -wall_offset.y, wall_length.x, WALL_THICKNESS),
+        (wall_offset.x, 0.0, WALL_THICKNESS, wall_length.y),
+        (-wall_offset.x, 0.0, WALL_THICKNESS, wall_length.y),
+    ];
+
+    for (x, y, width, height) in walls.into_iter().chain(OBSTACLES) {
+        let size = Vec2::new(width, height);
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: OBSTACLE_COLOR,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_xyz(x, y, 0.0),
+                ..default()
+            })
+            .insert(Obstacle)
+            .insert(Name::new("Obstacle"))
+            .insert(HitBox(size));
+    }
+
+    for (x, y) in SPAWN_POINTS {
+        commands
+            .spawn()
+            .insert(SpawnPoint)
+            .insert(Name::new("SpawnPoint"))
+            .insert(Transform::from_xyz(x, y, 0.0));
+    }
+}
+
+/// Pushes the player and enemies out of obstacles along the axis of least penetration.
+fn obstacle_collisions(
+    obstacle_query: Query<(&Transform, &HitBox), With<Obstacle>>,
+    mut actor_query: Query<
+        (&mut Transform, &HitBox),
+        (Or<(With<Player>, With<Enemy>)>, Without<Obstacle>),
+    >,
+) {
+    for (mut actor_transform, actor_hit_box) in actor_query.iter_mut() {
+        for (obstacle_transform, obstacle_hit_box) in obstacle_query.iter() {
+            let delta =
+                actor_transform.translation.truncate() - obstacle_transform.translation.truncate();
+            let penetration = (**actor_hit_box + **obstacle_hit_box) / 2.0 - delta.abs();
+
+            if penetration.x > 0.0 && penetration.y > 0.0 {
+                if penetration.x < penetration.y {
+                    actor_transform.translation.x += penetration.x * delta.x.signum();
+                } else {
+                    actor_transform.translation.y += penetration.y * delta.y.signum();
+                }
+            }
+        }
+    }
+}
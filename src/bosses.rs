@@ -0,0 +1,513 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::AiState,
+    app_state::AppState,
+    components::{
+        AttackTimer, BaseColor, Boss, ContactCooldown, ContactRange, CurrentPhase, Damage, Enemy,
+        EnemyBehaviour, Health, HitBox, KnockbackVelocity, Mass, MaxHealth, Player, Speed,
+    },
+    enemies::{enemy_movement, spawn_enemy},
+    enemy_kinds::{EnemyKinds, EnemyKindsHandle},
+    flow_field::FlowField,
+    loot::{Loot, LootEntry, LootTable},
+    ranged::spawn_projectile,
+    spawning::SpawnPlacement,
+    status_effects::{OnHitEffect, StatusEffects},
+    util::{finished_timer, VectorMath},
+    waves::WaveStarted,
+};
+
+// Constants
+const BOSS_WAVE_INTERVAL: u32 = 5;
+const BOSS_HEALTH: i32 = 600;
+const BOSS_SIZE: f32 = 80.0;
+const BOSS_CONTACT_DAMAGE: i32 = 2;
+const BOSS_CONTACT_RANGE: f32 = 10.0;
+const BOSS_CONTACT_COOLDOWN: f32 = 1.0;
+const BOSS_MASS: f32 = 10.0;
+
+const BOSS_PROJECTILE_SPEED: f32 = 220.0;
+const BOSS_PROJECTILE_DAMAGE: i32 = 1;
+const SUMMON_THREAT_BUDGET: u32 = 2;
+const SUMMON_DISTANCE: f32 = 100.0;
+
+const BOSS_PHASES: [BossPhase; 3] = [
+    BossPhase {
+        health_threshold: 1.0,
+        color: Color::rgb(0.2, 0.6, 0.3),
+        speed: 50.0,
+        movement: BossMovement::Chase,
+        attack: BossAttack::Radial { projectiles: 12 },
+        attack_interval: 3.0,
+        summons: 0,
+    },
+    BossPhase {
+        health_threshold: 0.66,
+        color: Color::rgb(0.3, 0.4, 0.7),
+        speed: 140.0,
+        movement: BossMovement::Orbit { radius: 350.0 },
+        attack: BossAttack::Spread {
+            projectiles: 5,
+            angle: PI / 4.0,
+        },
+        attack_interval: 1.5,
+        summons: 3,
+    },
+    BossPhase {
+        health_threshold: 0.33,
+        color: Color::rgb(0.7, 0.1, 0.4),
+        speed: 200.0,
+        movement: BossMovement::Chase,
+        attack: BossAttack::Summon { adds: 2 },
+        attack_interval: 4.0,
+        summons: 4,
+    },
+];
+
+// Phases
+struct BossPhase {
+    /// The phase starts once health drops to this fraction of the boss' maximum health
+    health_threshold: f32,
+    color: Color,
+    speed: f32,
+    movement: BossMovement,
+    attack: BossAttack,
+    attack_interval: f32,
+    /// Adds summoned when the phase starts
+    summons: u32,
+}
+
+enum BossMovement {
+    /// Walks at the player along the flow field
+    Chase,
+    /// Circles the player at a fixed distance
+    Orbit { radius: f32 },
+}
+
+enum BossAttack {
+    /// Projectiles in every direction
+    Radial { projectiles: u32 },
+    /// Fan of projectiles aimed at the player
+    Spread { projectiles: u32, angle: f32 },
+    /// Calls in more enemies
+    Summon { adds: u32 },
+}
+
+// Resources
+/// Bosses whose wave has started but that found no free spot yet; they keep trying every frame.
+#[derive(Default, Deref, DerefMut)]
+struct PendingBosses(u32);
+
+// Plugin
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(spawn_boss)
+                .with_system(boss_phases)
+                .with_system(boss_movement.after(enemy_movement))
+                .with_system(boss_attacks.after(boss_phases)),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(reset_pending_bosses))
+        .init_resource::<PendingBosses>();
+    }
+}
+
+// Systems
+fn spawn_boss(
+    mut commands: Commands,
+    mut wave_started: EventReader<WaveStarted>,
+    mut pending_bosses: ResMut<PendingBosses>,
+    spawn_placement: SpawnPlacement,
+    player_transform: Query<&Transform, With<Player>>,
+) {
+    **pending_bosses += wave_started
+        .iter()
+        .filter(|WaveStarted(wave)| wave % BOSS_WAVE_INTERVAL == 0)
+        .count() as u32;
+
+    let player_position = player_transform.single().translation.truncate();
+    let size = Vec2::splat(BOSS_SIZE);
+
+    while **pending_bosses > 0 {
+        // Crowded surroundings free up as the player moves on, so try again next frame
+        let boss_position = match spawn_placement.find(player_position, size) {
+            Some(boss_position) => boss_position,
+            None => break,
+        };
+
+        **pending_bosses -= 1;
+
+        spawn_boss_at(&mut commands, boss_position);
+    }
+}
+
+/// Spawns a boss in its first phase at `position`.
+fn spawn_boss_at(commands: &mut Commands, position: Vec2) {
+    let size = Vec2::splat(BOSS_SIZE);
+    let phase = &BOSS_PHASES[0];
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: phase.color,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(0.0)),
+            ..default()
+        })
+        .insert(Enemy)
+        .insert(Boss)
+        .insert(Name::new("Boss"))
+        .insert(Health(BOSS_HEALTH))
+        .insert(MaxHealth(BOSS_HEALTH))
+        .insert(Speed(phase.speed))
+        .insert(Damage(BOSS_CONTACT_DAMAGE))
+        .insert(ContactRange(BOSS_CONTACT_RANGE))
+        .insert(ContactCooldown(finished_timer(BOSS_CONTACT_COOLDOWN)))
+        .insert(HitBox(size))
+        .insert(Mass(BOSS_MASS))
+        .insert(KnockbackVelocity(Vec2::ZERO))
+        .insert(BaseColor(phase.color))
+        .insert(StatusEffects::default())
+        .insert(OnHitEffect(None))
+        .insert(EnemyBehaviour::Chase)
+        .insert(boss_loot_table())
+        .insert(CurrentPhase(0))
+        .insert(AttackTimer(Timer::from_seconds(
+            phase.attack_interval,
+            true,
+        )));
+}
+
+fn reset_pending_bosses(mut pending_bosses: ResMut<PendingBosses>) {
+    **pending_bosses = 0;
+}
+
+/// Advances bosses to the next phase once their health drops below its threshold.
+fn boss_phases(
+    mut commands: Commands,
+    enemy_kinds: Res<Assets<EnemyKinds>>,
+    enemy_kinds_handle: Res<EnemyKindsHandle>,
+    spawn_placement: SpawnPlacement,
+    mut boss_query: Query<
+        (
+            &Transform,
+            &Health,
+            &MaxHealth,
+            &mut CurrentPhase,
+            &mut Speed,
+            &mut BaseColor,
+            &mut Sprite,
+            &mut AttackTimer,
+        ),
+        With<Boss>,
+    >,
+) {
+    for (
+        boss_transform,
+        health,
+        max_health,
+        mut current_phase,
+        mut speed,
+        mut base_color,
+        mut sprite,
+        mut attack_timer,
+    ) in boss_query.iter_mut()
+    {
+        let health_fraction = **health as f32 / **max_health as f32;
+
+        while let Some(phase) = BOSS_PHASES.get(**current_phase + 1) {
+            if health_fraction > phase.health_threshold {
+                break;
+            }
+
+            **current_phase += 1;
+            **speed = phase.speed;
+            **base_color = phase.color;
+            sprite.color = phase.color;
+            **attack_timer = Timer::from_seconds(phase.attack_interval, true);
+
+            if let Some(enemy_kinds) = enemy_kinds.get(&**enemy_kinds_handle) {
+                summon_adds(
+                    &mut commands,
+                    &spawn_placement,
+                    enemy_kinds,
+                    boss_transform.translation.truncate(),
+                    phase.summons,
+                );
+            }
+        }
+    }
+}
+
+fn boss_movement(
+    time: Res<Time>,
+    flow_field: Res<FlowField>,
+    player_transform: Query<&Transform, With<Player>>,
+    mut boss_query: Query<
+        (&mut Transform, &Speed, &CurrentPhase, &StatusEffects),
+        (With<Boss>, Without<Player>),
+    >,
+) {
+    let player_position = player_transform.single().translation.truncate();
+
+    for (mut boss_transform, speed, current_phase, status_effects) in boss_query.iter_mut() {
+        let boss_position = boss_transform.translation.truncate();
+
+        let direction = match BOSS_PHASES[**current_phase].movement {
+            BossMovement::Chase => flow_field
+                .direction_at(boss_position)
+                .unwrap_or_else(|| boss_position.scaled_vector_to(&player_position, 1.0)),
+            BossMovement::Orbit { radius } => {
+                let outwards = player_position.scaled_vector_to(&boss_position, 1.0);
+                let tangent = outwards.perp();
+                let correction = (radius - boss_position.distance(player_position)) / radius;
+
+                (tangent + outwards * correction).normalize_or_zero()
+            }
+        };
+
+        boss_transform.translation +=
+            (direction * **speed * status_effects.speed_multiplier() * time.delta_seconds())
+                .extend(0.0);
+    }
+}
+
+fn boss_attacks(
+    mut commands: Commands,
+    time: Res<Time>,
+    enemy_kinds: Res<Assets<EnemyKinds>>,
+    enemy_kinds_handle: Res<EnemyKindsHandle>,
+    spawn_placement: SpawnPlacement,
+    player_transform: Query<&Transform, With<Player>>,
+    mut boss_query: Query<
+        (&Transform, &CurrentPhase, &mut AttackTimer, &StatusEffects),
+        (With<Boss>, Without<Player>),
+    >,
+) {
+    let player_position = player_transform.single().translation.truncate();
+
+    for (boss_transform, current_phase, mut attack_timer, status_effects) in boss_query.iter_mut() {
+        if status_effects.is_frozen() {
+            continue;
+        }
+
+        attack_timer.tick(time.delta());
+
+        if !attack_timer.just_finished() {
+            continue;
+        }
+
+        let boss_position = boss_transform.translation.truncate();
+        let aim = boss_position.scaled_vector_to(&player_position, BOSS_PROJECTILE_SPEED);
+
+        match BOSS_PHASES[**current_phase].attack {
+            BossAttack::Radial { projectiles } => {
+                for i in 0..projectiles {
+                    let angle = i as f32 / projectiles as f32 * 2.0 * PI;
+
+                    spawn_projectile(
+                        &mut commands,
+                        boss_transform.translation,
+                        aim.rotated_by(angle),
+                        BOSS_PROJECTILE_DAMAGE,
+                        None,
+                    );
+                }
+            }
+            BossAttack::Spread { projectiles, angle } => {
+                for i in 0..projectiles {
+                    // A lone projectile has nothing to fan out and flies straight at the player
+                    let offset = if projectiles > 1 {
+                        (i as f32 / (projectiles - 1) as f32 - 0.5) * angle
+                    } else {
+                        0.0
+                    };
+
+                    spawn_projectile(
+                        &mut commands,
+                        boss_transform.translation,
+                        aim.rotated_by(offset),
+                        BOSS_PROJECTILE_DAMAGE,
+                        None,
+                    );
+                }
+            }
+            BossAttack::Summon { adds } => {
+                if let Some(enemy_kinds) = enemy_kinds.get(&**enemy_kinds_handle) {
+                    summon_adds(
+                        &mut commands,
+                        &spawn_placement,
+                        enemy_kinds,
+                        boss_position,
+                        adds,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn boss_loot_table() -> LootTable {
+    LootTable {
+        rolls: 6,
+        entries: vec![
+            LootEntry {
+                weight: 2,
+                drop: Some(Loot::Health(2)),
+            },
+            LootEntry {
+                weight: 2,
+                drop: Some(Loot::Ammo(30)),
+            },
+            LootEntry {
+                weight: 1,
+                drop: Some(Loot::PowerUp {
+                    speed_multiplier: 1.5,
+                    duration: 10.0,
+                }),
+            },
+            LootEntry {
+                weight: 3,
+                drop: Some(Loot::Currency(10)),
+            },
+        ],
+    }
+}
+
+/// Calls in up to `adds` enemies around `position`. Adds that find no free spot next to the boss,
+/// e.g. with its back to a wall, are left out.
+fn summon_adds(
+    commands: &mut Commands,
+    spawn_placement: &SpawnPlacement,
+    enemy_kinds: &EnemyKinds,
+    position: Vec2,
+    adds: u32,
+) {
+    for _ in 0..adds {
+        let enemy_kind = match enemy_kinds.choose(SUMMON_THREAT_BUDGET) {
+            Some(enemy_kind) => enemy_kind,
+            None => continue,
+        };
+
+        if let Some(add_position) =
+            spawn_placement.find_around(position, SUMMON_DISTANCE, Vec2::splat(enemy_kind.size))
+        {
+            spawn_enemy(
+                commands,
+                enemy_kind,
+                add_position.extend(0.0),
+                AiState::Chase,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::sprite::collide_aabb::collide;
+
+    use super::*;
+    use crate::{
+        arena::{ArenaPlugin, ARENA_HALF_EXTENTS},
+        components::Obstacle,
+        enemies::enemy_damage,
+        player::spawn_player,
+        spatial_grid::{update_spatial_grid, SpatialGridPlugin},
+    };
+
+    #[test]
+    fn boss_in_reach_hurts_player() {
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins)
+            .add_state(AppState::Playing)
+            .add_plugin(SpatialGridPlugin)
+            .add_startup_system(spawn_player)
+            .add_startup_system(|mut commands: Commands| {
+                spawn_boss_at(&mut commands, Vec2::ZERO);
+            })
+            .add_system(enemy_damage.after(update_spatial_grid));
+        // The spatial grid only fills up once the state is entered on the first update
+        app.update();
+        app.update();
+
+        let (health, max_health) = app
+            .world
+            .query_filtered::<(&Health, &MaxHealth), With<Player>>()
+            .iter(&app.world)
+            .next()
+            .unwrap();
+
+        assert_eq!(**health, **max_health - BOSS_CONTACT_DAMAGE);
+    }
+
+    /// Right next to the arena's right wall, so most spots around the boss are out of bounds.
+    fn boss_by_wall() -> Vec2 {
+        Vec2::new(ARENA_HALF_EXTENTS.x - 50.0, 0.0)
+    }
+
+    fn summon_by_wall(mut commands: Commands, spawn_placement: SpawnPlacement) {
+        summon_adds(
+            &mut commands,
+            &spawn_placement,
+            &EnemyKinds::bundled(),
+            boss_by_wall(),
+            20,
+        );
+    }
+
+    #[test]
+    fn adds_stay_clear_of_walls() {
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins)
+            .add_state(AppState::Playing)
+            .init_resource::<Windows>()
+            .add_plugin(ArenaPlugin)
+            .add_system(summon_by_wall);
+        app.update();
+
+        let obstacles: Vec<(Vec3, Vec2)> = app
+            .world
+            .query_filtered::<(&Transform, &HitBox), With<Obstacle>>()
+            .iter(&app.world)
+            .map(|(transform, hit_box)| (transform.translation, **hit_box))
+            .collect();
+        let adds: Vec<(Vec3, Vec2)> = app
+            .world
+            .query_filtered::<(&Transform, &HitBox), With<Enemy>>()
+            .iter(&app.world)
+            .map(|(transform, hit_box)| (transform.translation, **hit_box))
+            .collect();
+
+        assert!(!adds.is_empty());
+
+        for (add_translation, add_size) in &adds {
+            let add_position = add_translation.truncate();
+
+            assert!(add_position.distance(boss_by_wall()) <= SUMMON_DISTANCE + 0.01);
+            assert!(add_position
+                .abs()
+                .cmple(ARENA_HALF_EXTENTS - *add_size / 2.0)
+                .all());
+
+            for (obstacle_translation, obstacle_size) in &obstacles {
+                assert!(collide(
+                    *add_translation,
+                    *add_size,
+                    *obstacle_translation,
+                    *obstacle_size
+                )
+                .is_none());
+            }
+        }
+    }
+}
@@ -1,17 +1,28 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+use serde::Deserialize;
 
 pub struct InspectionPlugin;
 
 impl Plugin for InspectionPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.register_inspectable::<Health>()
+            .register_inspectable::<MaxHealth>()
             .register_inspectable::<Invincible>()
             .register_inspectable::<Speed>()
             .register_inspectable::<Damage>()
             .register_inspectable::<CurrentAmmo>()
             .register_inspectable::<MaximumAmmo>()
-            .register_inspectable::<Reloading>();
+            .register_inspectable::<Reloading>()
+            .register_inspectable::<Knockback>()
+            .register_inspectable::<Mass>()
+            .register_inspectable::<Stunned>()
+            .register_inspectable::<Acceleration>()
+            .register_inspectable::<Friction>()
+            .register_inspectable::<TurnRate>()
+            .register_inspectable::<ContactRange>()
+            .register_inspectable::<WindingUp>()
+            .register_inspectable::<CurrentPhase>();
     }
 }
 
@@ -22,16 +33,44 @@ pub struct Player;
 pub struct Enemy;
 #[derive(Component)]
 pub struct Weapon;
+#[derive(Component)]
+pub struct Boss;
+#[derive(Component)]
+pub struct Obstacle;
+#[derive(Component)]
+pub struct SpawnPoint;
+#[derive(Component)]
+pub struct HealthBar;
+#[derive(Component)]
+pub struct HealthBarFill;
+#[derive(Component)]
+pub struct EnemyProjectile;
 
 // Stats
 #[derive(Component, Deref, DerefMut, Inspectable)]
 pub struct Health(pub i32);
 #[derive(Component, Deref, DerefMut, Inspectable)]
+pub struct MaxHealth(pub i32);
+#[derive(Component, Deref, DerefMut, Inspectable)]
 pub struct Invincible(pub bool);
 #[derive(Component, Deref, DerefMut)]
 pub struct InvincibilityTimer(pub Timer);
 #[derive(Component, Deref, DerefMut, Inspectable)]
 pub struct Speed(pub f32);
+#[derive(Component)]
+pub struct SpeedBoost {
+    pub multiplier: f32,
+    pub timer: Timer,
+}
+/// Present while the player is dodge rolling
+#[derive(Component)]
+pub struct Roll {
+    pub direction: Vec2,
+    pub timer: Timer,
+}
+/// Runs from the start of one roll until the next one is allowed
+#[derive(Component, Deref, DerefMut)]
+pub struct RollCooldown(pub Timer);
 #[derive(Component, Deref, DerefMut, Inspectable)]
 pub struct Damage(pub i32);
 #[derive(Component, Deref, DerefMut, Inspectable)]
@@ -44,15 +83,107 @@ pub struct FireDelayTimer(pub Timer);
 pub struct ReloadTimer(pub Timer);
 #[derive(Component, Deref, DerefMut, Inspectable)]
 pub struct Reloading(pub bool);
+#[derive(Component, Deref, DerefMut, Inspectable)]
+pub struct Knockback(pub f32);
+#[derive(Component, Deref, DerefMut, Inspectable)]
+pub struct Mass(pub f32);
+#[derive(Component, Deref, DerefMut)]
+pub struct KnockbackVelocity(pub Vec2);
+#[derive(Component, Deref, DerefMut, Inspectable)]
+pub struct Stunned(pub bool);
+#[derive(Component, Deref, DerefMut)]
+pub struct StunTimer(pub Timer);
+#[derive(Component, Deref, DerefMut)]
+pub struct HitBox(pub Vec2);
+#[derive(Component, Deref, DerefMut)]
+pub struct BaseColor(pub Color);
+#[derive(Component, Deref, DerefMut)]
+pub struct Velocity(pub Vec2);
+#[derive(Component, Deref, DerefMut, Inspectable)]
+pub struct Acceleration(pub f32);
+/// How quickly something coasts to a halt once nothing drives it, in units per second squared
+#[derive(Component, Deref, DerefMut, Inspectable)]
+pub struct Friction(pub f32);
+#[derive(Component, Deref, DerefMut, Inspectable)]
+pub struct TurnRate(pub f32);
+#[derive(Component, Deref, DerefMut)]
+pub struct LifetimeTimer(pub Timer);
+#[derive(Component, Deref, DerefMut)]
+pub struct HealthBarTimer(pub Timer);
+#[derive(Component, Deref, DerefMut)]
+pub struct AttackTimer(pub Timer);
+/// How far beyond its hit box an enemy's contact attack reaches
+#[derive(Component, Deref, DerefMut, Inspectable)]
+pub struct ContactRange(pub f32);
+#[derive(Component, Deref, DerefMut)]
+pub struct ContactCooldown(pub Timer);
+#[derive(Component, Deref, DerefMut)]
+pub struct WindUpTimer(pub Timer);
+#[derive(Component, Deref, DerefMut, Inspectable)]
+pub struct WindingUp(pub bool);
+#[derive(Component, Deref, DerefMut, Inspectable)]
+pub struct CurrentPhase(pub usize);
+
+// Behaviours
+#[derive(Clone, Copy, Component, Deserialize)]
+pub enum EnemyBehaviour {
+    /// Walks straight at the player
+    Chase,
+    /// Keeps `preferred_distance` to the player and shoots at them every `attack_interval`
+    /// seconds, standing still for `wind_up` seconds before each shot
+    Ranged {
+        preferred_distance: f32,
+        attack_interval: f32,
+        wind_up: f32,
+        projectile_speed: f32,
+        projectile_damage: i32,
+    },
+    /// Blows up once the player is within `radius`, dealing its contact damage
+    Explode { radius: f32 },
+}
+
+/// Extra behaviour when an enemy dies.
+#[derive(Clone, Copy, Deserialize)]
+pub enum OnDeath {
+    /// Falls apart into `children` smaller, faster and weaker copies, which split again themselves
+    /// for the remaining `generations`
+    Split { children: u32, generations: u32 },
+}
+
+/// What happens to an enemy that falls too far behind the player.
+#[derive(Clone, Copy, Component, Default, Deserialize)]
+pub enum LeashPolicy {
+    /// Removed for good, without dropping loot
+    Despawn,
+    /// Moved to a fresh spawn position ahead of the player
+    #[default]
+    Teleport,
+}
 
 // Properties
 #[derive(Component)]
 pub struct HealthText;
 #[derive(Component)]
+pub struct Hud;
+#[derive(Component)]
+pub struct MainMenuScreen;
+#[derive(Component)]
+pub struct PauseScreen;
+#[derive(Component)]
+pub struct GameOverScreen;
+#[derive(Component)]
 pub struct WeaponText;
 #[derive(Component)]
 pub struct AmmoText;
 #[derive(Component)]
 pub struct EnemyText;
 #[derive(Component)]
+pub struct WaveText;
+#[derive(Component)]
+pub struct CurrencyText;
+#[derive(Component)]
+pub struct BossHealthBar;
+#[derive(Component)]
+pub struct BossHealthBarFill;
+#[derive(Component)]
 pub struct MainCamera;
@@ -0,0 +1,276 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use rand::{prelude::SliceRandom, Rng};
+use serde::Deserialize;
+
+use crate::{
+    app_state::AppState,
+    components::{Health, InvincibilityTimer, Invincible, Mass, MaxHealth, OnDeath, Player, Speed},
+    enemies::{EnemyKilled, SplitsInto},
+    enemy_kinds::EnemyKind,
+    loot::LootTable,
+    player::hurt_player,
+};
+
+// Constants
+const ELITE_CHANCE_BASE: f32 = 0.02;
+const ELITE_CHANCE_PER_WAVE: f32 = 0.03;
+const ELITE_CHANCE_MAX: f32 = 0.4;
+// Chance for every affix after the first to roll another one
+const EXTRA_AFFIX_CHANCE: f32 = 0.3;
+
+const ELITE_HEALTH_MULTIPLIER: f32 = 1.5;
+const ELITE_EXTRA_LOOT_ROLLS: u32 = 2;
+const ELITE_OUTLINE_WIDTH: f32 = 3.0;
+const ELITE_OUTLINE_COLOR: Color = Color::GOLD;
+
+const FAST_SPEED_MULTIPLIER: f32 = 1.5;
+const ARMORED_HEALTH_MULTIPLIER: f32 = 2.0;
+const ARMORED_MASS_MULTIPLIER: f32 = 2.0;
+const REGENERATION_INTERVAL: f32 = 0.25;
+const SPLIT_COUNT: u32 = 2;
+const EXPLOSION_RADIUS: f32 = 100.0;
+const EXPLOSION_DAMAGE: i32 = 2;
+
+// Affixes
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum Affix {
+    /// Moves faster
+    Fast,
+    /// Has more health and is harder to knock back
+    Armored,
+    /// Slowly heals back to full health
+    Regenerating,
+    /// Falls apart into regular enemies of its kind on death
+    Splitting,
+    /// Blows up on death, hurting the player when close
+    Explosive,
+}
+
+const AFFIXES: [Affix; 5] = [
+    Affix::Fast,
+    Affix::Armored,
+    Affix::Regenerating,
+    Affix::Splitting,
+    Affix::Explosive,
+];
+
+#[derive(Clone, Component, Deref)]
+pub struct Elite(Vec<Affix>);
+
+#[derive(Component, Deref, DerefMut)]
+struct RegenerationTimer(Timer);
+
+/// Chance for an enemy spawned during `wave` to be an elite.
+pub fn elite_chance(wave: u32) -> f32 {
+    (ELITE_CHANCE_BASE + ELITE_CHANCE_PER_WAVE * wave.saturating_sub(1) as f32)
+        .min(ELITE_CHANCE_MAX)
+}
+
+/// Picks at least one distinct affix, with a shrinking chance for every additional one.
+pub fn roll_affixes(rng: &mut impl Rng) -> Vec<Affix> {
+    let mut affixes = AFFIXES.to_vec();
+    affixes.shuffle(rng);
+
+    let mut count = 1;
+    while count < affixes.len() && rng.gen::<f32>() < EXTRA_AFFIX_CHANCE {
+        count += 1;
+    }
+    affixes.truncate(count);
+
+    affixes
+}
+
+// Plugin
+pub struct ElitePlugin;
+
+impl Plugin for ElitePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(regenerate_elites)
+                .with_system(elite_death_effects),
+        );
+    }
+}
+
+/// Turns a freshly spawned enemy of `enemy_kind` into an elite with the kind's affixes, scaling
+/// its stats, improving its loot and giving it an outline.
+pub fn make_elite(enemy: &mut EntityCommands, enemy_kind: &EnemyKind) {
+    let affixes = &enemy_kind.affixes;
+
+    let mut health = enemy_kind.health as f32 * ELITE_HEALTH_MULTIPLIER;
+    let mut speed = enemy_kind.speed;
+    let mut mass = enemy_kind.mass;
+
+    for affix in affixes {
+        match affix {
+            Affix::Fast => speed *= FAST_SPEED_MULTIPLIER,
+            Affix::Armored => {
+                health *= ARMORED_HEALTH_MULTIPLIER;
+                mass *= ARMORED_MASS_MULTIPLIER;
+            }
+            Affix::Regenerating => {
+                enemy.insert(RegenerationTimer(Timer::from_seconds(
+                    REGENERATION_INTERVAL,
+                    true,
+                )));
+            }
+            Affix::Splitting => {
+                if let Some((split_kind, count)) = affix_split(enemy_kind) {
+                    enemy.insert(SplitsInto {
+                        enemy_kind: split_kind,
+                        count,
+                    });
+                }
+            }
+            Affix::Explosive => {}
+        }
+    }
+
+    // Elites drop more often and never roll empty
+    let loot = LootTable {
+        rolls: enemy_kind.loot.rolls + ELITE_EXTRA_LOOT_ROLLS,
+        entries: enemy_kind
+            .loot
+            .entries
+            .iter()
+            .filter(|entry| entry.drop.is_some())
+            .cloned()
+            .collect(),
+    };
+
+    let size = Vec2::splat(enemy_kind.size + ELITE_OUTLINE_WIDTH * 2.0);
+
+    enemy
+        .insert(Name::new(format!("Elite {}", enemy_kind.name)))
+        .insert(Health(health as i32))
+        .insert(MaxHealth(health as i32))
+        .insert(Speed(speed))
+        .insert(Mass(mass))
+        .insert(loot)
+        .insert(Elite(affixes.clone()))
+        .with_children(|parent| {
+            parent.spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: ELITE_OUTLINE_COLOR,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                // Just behind the enemy, so only the border shows
+                transform: Transform::from_xyz(0.0, 0.0, -0.1),
+                ..default()
+            });
+        });
+}
+
+/// What the `Splitting` affix makes `enemy_kind` fall apart into: a single generation of the
+/// usual smaller, faster and weaker children. Kinds that split on their own already do, and keep
+/// their own split.
+fn affix_split(enemy_kind: &EnemyKind) -> Option<(EnemyKind, u32)> {
+    if enemy_kind.split().is_some() {
+        return None;
+    }
+
+    let mut splitting_kind = enemy_kind.clone();
+    splitting_kind.on_death = Some(OnDeath::Split {
+        children: SPLIT_COUNT,
+        generations: 1,
+    });
+
+    splitting_kind.split()
+}
+
+// Systems
+fn regenerate_elites(
+    time: Res<Time>,
+    mut elite_query: Query<(&mut Health, &MaxHealth, &mut RegenerationTimer)>,
+) {
+    for (mut health, max_health, mut regeneration_timer) in elite_query.iter_mut() {
+        regeneration_timer.tick(time.delta());
+
+        if regeneration_timer.just_finished() && **health < **max_health {
+            **health += 1;
+        }
+    }
+}
+
+fn elite_death_effects(
+    mut enemy_killed: EventReader<EnemyKilled>,
+    mut player_query: Query<
+        (
+            &Transform,
+            &mut Health,
+            &mut Invincible,
+            &mut InvincibilityTimer,
+        ),
+        With<Player>,
+    >,
+) {
+    let (player_transform, mut player_health, mut invincible, mut invincibility_timer) =
+        player_query.single_mut();
+    let player_position = player_transform.translation.truncate();
+
+    for enemy_killed in enemy_killed.iter() {
+        let explosive = matches!(
+            &enemy_killed.elite,
+            Some(elite) if elite.contains(&Affix::Explosive)
+        );
+
+        if explosive && enemy_killed.position.distance(player_position) <= EXPLOSION_RADIUS {
+            hurt_player(
+                &mut player_health,
+                &mut invincible,
+                &mut invincibility_timer,
+                EXPLOSION_DAMAGE,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{prelude::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::enemy_kinds::EnemyKinds;
+
+    #[test]
+    fn elite_chance_rises_up_to_cap() {
+        assert_eq!(elite_chance(1), ELITE_CHANCE_BASE);
+        assert!(elite_chance(5) > elite_chance(4));
+        assert_eq!(elite_chance(1000), ELITE_CHANCE_MAX);
+    }
+
+    #[test]
+    fn affixes_are_distinct() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..100 {
+            let affixes = roll_affixes(&mut rng);
+
+            assert!(!affixes.is_empty());
+            for (i, affix) in affixes.iter().enumerate() {
+                assert!(!affixes[i + 1..].contains(affix));
+            }
+        }
+    }
+
+    #[test]
+    fn splitting_affix_adds_one_scaled_generation() {
+        let enemy_kinds = EnemyKinds::bundled();
+
+        for kind in enemy_kinds.iter() {
+            match affix_split(kind) {
+                Some((child, count)) => {
+                    assert_eq!(count, SPLIT_COUNT);
+                    assert!(child.size < kind.size);
+                    assert!(child.speed > kind.speed);
+                    assert!(child.health <= kind.health);
+                    assert!(child.split().is_none());
+                }
+                // The kind's own split stays in place
+                None => assert!(kind.split().is_some()),
+            }
+        }
+    }
+}
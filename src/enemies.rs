@@ -1,135 +1,512 @@
 use std::f32::consts::PI;
 
-use bevy::{core::FixedTimestep, prelude::*, sprite::collide_aabb::collide};
+use bevy::{prelude::*, sprite::collide_aabb::collide, utils::HashMap};
 use rand::random;
 
 use crate::{
-    components::{Enemy, Health, InvincibilityTimer, Invincible, Player, Speed},
-    player::player_movement,
-    util::VectorMath,
+    ai::{insert_ai, AiState, WanderTarget},
+    app_state::{despawn_all, AppState},
+    components::{
+        Acceleration, AttackTimer, BaseColor, Boss, ContactCooldown, ContactRange, Damage, Enemy,
+        EnemyBehaviour, Health, HitBox, InvincibilityTimer, Invincible, KnockbackVelocity, Mass,
+        MaxHealth, Player, Speed, StunTimer, Stunned, TurnRate, Velocity, WindUpTimer, WindingUp,
+    },
+    elites::{make_elite, Elite},
+    enemy_kinds::EnemyKind,
+    flow_field::{update_flow_field, FlowField},
+    health_bars::spawn_health_bar,
+    loot::LootTable,
+    player::hurt_player,
+    spatial_grid::{update_spatial_grid, SpatialGrid},
+    status_effects::{OnHitEffect, StatusEffects},
+    util::{finished_timer, VectorMath},
+    weapons::shoot_weapon,
 };
 
 // Constants
-const ENEMY_COLOR: Color = Color::RED;
-const ENEMY_SPEED: f32 = 90.0;
-const ENEMY_HEALTH: i32 = 25;
+const SEPARATION_DISTANCE: f32 = 20.0;
+const SEPARATION_WEIGHT: f32 = 1.5;
+const SPLIT_SCATTER: f32 = 25.0;
+// Enemies further than this from the player's hit box are neither in contact range nor close
+// enough to blow up, so contact damage does not look at them
+const NEARBY_MARGIN: f32 = 100.0;
+// The grid holds positions from the start of the frame, so collisions look a bit further to
+// catch enemies that moved into each other since
+const COLLISION_MARGIN: f32 = 16.0;
 
-const INITIAL_ENEMY_DISTANCE: f32 = 750.0;
-const MAXIMUM_ENEMY_COUNT: usize = 10;
-const ENEMY_SPAWN_DELAY: f64 = 1.0;
+const HIT_STUN_TIME: f32 = 0.2;
+// Exponential decay rate of knockback velocity, per second
+const KNOCKBACK_DAMPING: f32 = 8.0;
+
+// Events
+pub struct EnemyKilled {
+    pub position: Vec2,
+    pub loot_table: LootTable,
+    pub elite: Option<Elite>,
+}
+
+// Death hooks
+/// Spawns `count` enemies of `enemy_kind` where this enemy dies.
+#[derive(Component)]
+pub struct SplitsInto {
+    pub enemy_kind: EnemyKind,
+    pub count: u32,
+}
 
 // Plugin
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::new()
-                .with_run_criteria(FixedTimestep::step(ENEMY_SPAWN_DELAY))
-                .with_system(enemy_spawner),
-        )
-        .add_system(enemy_movement.after(player_movement))
-        .add_system(enemy_damage.after(enemy_movement));
+        app.add_event::<EnemyKilled>()
+            .add_system_set(
+                SystemSet::on_update(AppState::Playing)
+                    .with_system(
+                        enemy_movement
+                            .after(update_flow_field)
+                            .after(update_spatial_grid),
+                    )
+                    .with_system(enemy_knockback.after(enemy_movement))
+                    .with_system(enemy_damage.after(enemy_knockback))
+                    // Contact damage needs to see the overlap before it gets resolved
+                    .with_system(enemy_collisions.after(enemy_damage))
+                    .with_system(enemy_deaths.after(shoot_weapon).after(enemy_damage)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::GameOver).with_system(despawn_all::<Enemy>),
+            );
     }
 }
 
 // Systems
-fn enemy_spawner(
-    mut commands: Commands,
-    player_transform: Query<&Transform, With<Player>>,
-    enemies: Query<Entity, With<Enemy>>,
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    enemy_kind: &EnemyKind,
+    translation: Vec3,
+    ai_state: AiState,
 ) {
-    let enemy_count = enemies.iter().count();
+    let size = Vec2::splat(enemy_kind.size);
 
-    // TODO: Consider removing this mechanic when it turns out to be less fun than endless spawning
-    if enemy_count < MAXIMUM_ENEMY_COUNT {
-        let player_transform = player_transform.single();
+    let mut enemy = commands.spawn_bundle(SpriteBundle {
+        sprite: Sprite {
+            color: enemy_kind.color,
+            custom_size: Some(size),
+            ..default()
+        },
+        transform: Transform::from_translation(translation),
+        ..default()
+    });
 
-        // Create rotation Quad from rand
-        let angle = random::<f32>() * 2.0 * PI;
-        let x = INITIAL_ENEMY_DISTANCE * angle.cos();
-        let y = INITIAL_ENEMY_DISTANCE * angle.sin();
+    enemy
+        .insert(Enemy)
+        .insert(Name::new(enemy_kind.name.clone()))
+        .insert(Health(enemy_kind.health))
+        .insert(MaxHealth(enemy_kind.health))
+        .insert(Speed(enemy_kind.speed))
+        .insert(Velocity(Vec2::ZERO))
+        .insert(Acceleration(enemy_kind.acceleration))
+        .insert(TurnRate(enemy_kind.turn_rate))
+        .insert(Damage(enemy_kind.contact_damage))
+        .insert(ContactRange(enemy_kind.contact_range))
+        .insert(ContactCooldown(finished_timer(enemy_kind.contact_cooldown)))
+        .insert(HitBox(size))
+        .insert(Mass(enemy_kind.mass))
+        .insert(KnockbackVelocity(Vec2::ZERO))
+        .insert(Stunned(false))
+        .insert(StunTimer(Timer::from_seconds(HIT_STUN_TIME, false)))
+        .insert(BaseColor(enemy_kind.color))
+        .insert(StatusEffects::default())
+        .insert(OnHitEffect(enemy_kind.on_hit))
+        .insert(enemy_kind.behaviour)
+        .insert(enemy_kind.leash)
+        .insert(enemy_kind.loot.clone())
+        .with_children(|parent| spawn_health_bar(parent, size));
 
-        let enemy_translation = Vec3::new(
-            player_transform.translation.x + x,
-            player_transform.translation.y + y,
-            0.0,
-        );
+    if let EnemyBehaviour::Ranged {
+        attack_interval,
+        wind_up,
+        ..
+    } = enemy_kind.behaviour
+    {
+        enemy
+            .insert(AttackTimer(Timer::from_seconds(attack_interval, false)))
+            .insert(WindUpTimer(Timer::from_seconds(wind_up, false)))
+            .insert(WindingUp(false));
+    }
 
-        spawn_enemy(&mut commands, enemy_translation);
+    insert_ai(&mut enemy, &enemy_kind.ai, translation.truncate(), ai_state);
+
+    if let Some((child_kind, count)) = enemy_kind.split() {
+        enemy.insert(SplitsInto {
+            enemy_kind: child_kind,
+            count,
+        });
     }
-}
 
-fn spawn_enemy(commands: &mut Commands, translation: Vec3) {
-    commands
-        .spawn_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: ENEMY_COLOR,
-                ..default()
-            },
-            transform: Transform {
-                scale: Vec3::new(30.0, 30.0, 1.0),
-                translation,
-                ..default()
-            },
-            ..default()
-        })
-        .insert(Enemy)
-        .insert(Name::new("Enemy"))
-        .insert(Health(ENEMY_HEALTH))
-        .insert(Speed(ENEMY_SPEED));
+    if !enemy_kind.affixes.is_empty() {
+        make_elite(&mut enemy, enemy_kind);
+    }
 }
 
-// TODO: Add logic so that enemies can't be inside another enemy or the player
 pub fn enemy_movement(
     time: Res<Time>,
+    flow_field: Res<FlowField>,
+    spatial_grid: Res<SpatialGrid>,
     player_transform: Query<&Transform, With<Player>>,
-    mut enemy_query: Query<(&mut Transform, &Speed), (With<Enemy>, Without<Player>)>,
+    mut enemy_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut Velocity,
+            &Speed,
+            &Acceleration,
+            &TurnRate,
+            &HitBox,
+            &EnemyBehaviour,
+            Option<&WindingUp>,
+            &AiState,
+            &WanderTarget,
+            &StatusEffects,
+        ),
+        (With<Enemy>, Without<Boss>, Without<Player>),
+    >,
 ) {
     let player_position = player_transform.single().translation.truncate();
 
-    for (mut enemy_transform, enemy_speed) in enemy_query.iter_mut() {
-        let enemy_player_vector = enemy_transform
-            .translation
-            .truncate()
-            .scaled_vector_to(&player_position, **enemy_speed * time.delta_seconds());
+    for (
+        enemy_ent,
+        mut enemy_transform,
+        mut enemy_velocity,
+        enemy_speed,
+        enemy_acceleration,
+        enemy_turn_rate,
+        enemy_hit_box,
+        enemy_behaviour,
+        winding_up,
+        ai_state,
+        wander_target,
+        status_effects,
+    ) in enemy_query.iter_mut()
+    {
+        // Enemies stand still while telegraphing an attack or frozen, and are left to their
+        // knockback while stunned
+        if matches!(winding_up, Some(WindingUp(true)))
+            || *ai_state == AiState::Stunned
+            || status_effects.is_frozen()
+        {
+            **enemy_velocity = Vec2::ZERO;
+            continue;
+        }
+
+        let enemy_position = enemy_transform.translation.truncate();
+        let enemy_radius = enemy_hit_box.max_element() / 2.0;
 
-        enemy_transform.translation.x += enemy_player_vector.x;
-        enemy_transform.translation.y += enemy_player_vector.y;
+        let (steering_direction, speed_factor) = ai_state.steering(
+            enemy_behaviour,
+            enemy_position,
+            player_position,
+            **wander_target,
+            &flow_field,
+        );
+
+        // Boids-style separation: push away from every neighbour within range, harder when closer.
+        // The grid holds where everyone was at the start of the frame, so the order enemies move
+        // in does not matter
+        let mut separation = Vec2::ZERO;
+
+        for neighbour in
+            spatial_grid.query_radius(enemy_position, enemy_radius + SEPARATION_DISTANCE)
+        {
+            if neighbour.entity == enemy_ent {
+                continue;
+            }
+
+            let range = enemy_radius + neighbour.radius + SEPARATION_DISTANCE;
+            let distance = enemy_position.distance(neighbour.position);
+
+            if distance < range {
+                separation += neighbour.position.scaled_vector_to(&enemy_position, 1.0)
+                    * (1.0 - distance / range);
+            }
+        }
+
+        let desired_velocity = (steering_direction + separation * SEPARATION_WEIGHT)
+            .clamp_length_max(1.0)
+            * **enemy_speed
+            * speed_factor
+            * status_effects.speed_multiplier();
+
+        // Turning and speeding up take time, so fast enemies overshoot and can be circled
+        **enemy_velocity = enemy_velocity.steered_towards(
+            &desired_velocity,
+            **enemy_turn_rate * time.delta_seconds(),
+            **enemy_acceleration * time.delta_seconds(),
+        );
+
+        let enemy_movement_vector = **enemy_velocity * time.delta_seconds();
+
+        enemy_transform.translation.x += enemy_movement_vector.x;
+        enemy_transform.translation.y += enemy_movement_vector.y;
     }
 }
 
-fn enemy_damage(
+/// Moves enemies along their decaying knockback and lets them recover from hit-stun.
+fn enemy_knockback(
     time: Res<Time>,
+    mut enemy_query: Query<
+        (
+            &mut Transform,
+            &mut KnockbackVelocity,
+            Option<(&mut Stunned, &mut StunTimer)>,
+        ),
+        With<Enemy>,
+    >,
+) {
+    for (mut enemy_transform, mut knockback_velocity, stun) in enemy_query.iter_mut() {
+        if **knockback_velocity != Vec2::ZERO {
+            enemy_transform.translation +=
+                (**knockback_velocity * time.delta_seconds()).extend(0.0);
+
+            **knockback_velocity *= (-KNOCKBACK_DAMPING * time.delta_seconds()).exp();
+            if knockback_velocity.length_squared() < 1.0 {
+                **knockback_velocity = Vec2::ZERO;
+            }
+        }
+
+        if let Some((mut stunned, mut stun_timer)) = stun {
+            if **stunned {
+                stun_timer.tick(time.delta());
+
+                if stun_timer.just_finished() {
+                    **stunned = false;
+                }
+            }
+        }
+    }
+}
+
+/// Pushes overlapping enemies apart and out of the player, treating hit boxes as circles.
+pub fn enemy_collisions(
+    spatial_grid: Res<SpatialGrid>,
+    player_query: Query<(&Transform, &HitBox), With<Player>>,
+    mut enemy_query: Query<(Entity, &mut Transform, &HitBox), (With<Enemy>, Without<Player>)>,
+) {
+    let (player_transform, player_hit_box) = player_query.single();
+    let player_position = player_transform.translation.truncate();
+    let player_radius = player_hit_box.max_element() / 2.0;
+
+    // Pushes are summed up first, so every pair is resolved from where both enemies stood
+    let mut pushes: HashMap<Entity, Vec2> = HashMap::default();
+
+    for (enemy_ent, enemy_transform, enemy_hit_box) in enemy_query.iter() {
+        let enemy_position = enemy_transform.translation.truncate();
+        let enemy_radius = enemy_hit_box.max_element() / 2.0;
+
+        for neighbour in spatial_grid.query_radius(enemy_position, enemy_radius + COLLISION_MARGIN)
+        {
+            // Both enemies of a pair find each other, only the first one resolves it
+            if neighbour.entity <= enemy_ent {
+                continue;
+            }
+
+            if let Ok((_, neighbour_transform, neighbour_hit_box)) =
+                enemy_query.get(neighbour.entity)
+            {
+                let neighbour_position = neighbour_transform.translation.truncate();
+                let overlap = enemy_radius + neighbour_hit_box.max_element() / 2.0
+                    - enemy_position.distance(neighbour_position);
+
+                if overlap > 0.0 {
+                    // Perfectly stacked enemies have no direction to separate in, so pick one at
+                    // random
+                    let mut push =
+                        neighbour_position.scaled_vector_to(&enemy_position, overlap / 2.0);
+                    if push == Vec2::ZERO {
+                        push = Vec2::X.rotated_by(random::<f32>() * 2.0 * PI) * overlap / 2.0;
+                    }
+
+                    *pushes.entry(enemy_ent).or_default() += push;
+                    *pushes.entry(neighbour.entity).or_default() -= push;
+                }
+            }
+        }
+    }
+
+    for (enemy_ent, mut enemy_transform, enemy_hit_box) in enemy_query.iter_mut() {
+        if let Some(push) = pushes.get(&enemy_ent) {
+            enemy_transform.translation += push.extend(0.0);
+        }
+
+        let enemy_position = enemy_transform.translation.truncate();
+        let overlap = player_radius + enemy_hit_box.max_element() / 2.0
+            - enemy_position.distance(player_position);
+
+        if overlap > 0.0 {
+            let push = player_position.scaled_vector_to(&enemy_position, overlap);
+
+            enemy_transform.translation += push.extend(0.0);
+        }
+    }
+}
+
+pub fn enemy_damage(
+    time: Res<Time>,
+    spatial_grid: Res<SpatialGrid>,
     mut player_query: Query<
         (
             &Transform,
+            &HitBox,
             &mut Health,
             &mut Invincible,
             &mut InvincibilityTimer,
+            &mut StatusEffects,
         ),
         With<Player>,
     >,
-    enemy_transforms: Query<&Transform, (With<Enemy>, Without<Player>)>,
+    mut enemy_query: Query<
+        (
+            Entity,
+            &Transform,
+            &HitBox,
+            &Damage,
+            &ContactRange,
+            &mut ContactCooldown,
+            &EnemyBehaviour,
+            &OnHitEffect,
+            &StatusEffects,
+        ),
+        (With<Enemy>, Without<Player>),
+    >,
+    mut enemy_health_query: Query<&mut Health, (With<Enemy>, Without<Player>)>,
 ) {
-    let (player_transform, mut player_health, mut invincible, mut invincibility_timer) =
-        player_query.single_mut();
+    let (
+        player_transform,
+        player_hit_box,
+        mut player_health,
+        mut invincible,
+        mut invincibility_timer,
+        mut player_status_effects,
+    ) = player_query.single_mut();
+
+    // Frozen enemies hold their attack until they thaw
+    for (_, _, _, _, _, mut contact_cooldown, _, _, status_effects) in enemy_query.iter_mut() {
+        if !status_effects.is_frozen() {
+            contact_cooldown.tick(time.delta());
+        }
+    }
+
+    let nearby: Vec<Entity> = spatial_grid
+        .query_aabb(
+            player_transform.translation.truncate(),
+            **player_hit_box + Vec2::splat(NEARBY_MARGIN * 2.0),
+        )
+        .map(|entry| entry.entity)
+        .collect();
+
+    // Exploders go off whether or not the player can currently be hurt
+    for (
+        enemy_ent,
+        enemy_transform,
+        _,
+        enemy_damage,
+        _,
+        _,
+        enemy_behaviour,
+        on_hit_effect,
+        status_effects,
+    ) in nearby
+        .iter()
+        .filter_map(|enemy_ent| enemy_query.get(*enemy_ent).ok())
+    {
+        if status_effects.is_frozen() {
+            continue;
+        }
+
+        if let EnemyBehaviour::Explode { radius } = enemy_behaviour {
+            let distance = enemy_transform
+                .translation
+                .truncate()
+                .distance(player_transform.translation.truncate());
+
+            if distance <= *radius {
+                // Blowing up kills the exploder, `enemy_deaths` takes care of the rest
+                if let Ok(mut exploder_health) = enemy_health_query.get_mut(enemy_ent) {
+                    if **exploder_health <= 0 {
+                        continue;
+                    }
+
+                    **exploder_health = 0;
+                }
+
+                let hurt = hurt_player(
+                    &mut player_health,
+                    &mut invincible,
+                    &mut invincibility_timer,
+                    **enemy_damage,
+                );
+
+                if let (true, Some(effect)) = (hurt, **on_hit_effect) {
+                    player_status_effects.apply(effect);
+                }
+            }
+        }
+    }
+
+    // Of all enemies in reach and ready to attack, the hardest hitter lands the blow and the rest
+    // keep their attack for when the invincibility wears off
+    let mut attacker = None;
+
+    for (
+        enemy_ent,
+        enemy_transform,
+        enemy_hit_box,
+        enemy_damage,
+        contact_range,
+        contact_cooldown,
+        _,
+        on_hit_effect,
+        status_effects,
+    ) in nearby
+        .iter()
+        .filter_map(|enemy_ent| enemy_query.get(*enemy_ent).ok())
+    {
+        if status_effects.is_frozen() {
+            continue;
+        }
+
+        let in_reach = collide(
+            player_transform.translation,
+            **player_hit_box,
+            enemy_transform.translation,
+            **enemy_hit_box + Vec2::splat(**contact_range * 2.0),
+        )
+        .is_some();
+
+        if in_reach
+            && contact_cooldown.finished()
+            && !matches!(attacker, Some((_, damage, _)) if damage >= **enemy_damage)
+        {
+            attacker = Some((enemy_ent, **enemy_damage, **on_hit_effect));
+        }
+    }
 
     if !**invincible {
-        for enemy_transform in enemy_transforms.iter() {
-            if collide(
-                player_transform.translation,
-                Vec2::new(30.0, 30.0),
-                enemy_transform.translation,
-                Vec2::new(30.0, 30.0),
-            )
-            .is_some()
+        if let Some((attacker_ent, damage, on_hit_effect)) = attacker {
+            hurt_player(
+                &mut player_health,
+                &mut invincible,
+                &mut invincibility_timer,
+                damage,
+            );
+
+            if let Ok((_, _, _, _, _, mut contact_cooldown, _, _, _)) =
+                enemy_query.get_mut(attacker_ent)
             {
-                **player_health -= 1;
-                **invincible = true;
-                invincibility_timer.reset();
+                contact_cooldown.reset();
+            }
 
-                break;
+            if let Some(effect) = on_hit_effect {
+                player_status_effects.apply(effect);
             }
         }
     } else {
@@ -140,3 +517,50 @@ fn enemy_damage(
         }
     }
 }
+
+/// Removes every enemy whose health ran out, wherever the damage came from, and runs its death
+/// hooks.
+pub fn enemy_deaths(
+    mut commands: Commands,
+    mut enemy_killed: EventWriter<EnemyKilled>,
+    enemy_query: Query<
+        (
+            Entity,
+            &Transform,
+            &Health,
+            Option<&LootTable>,
+            Option<&Elite>,
+            Option<&SplitsInto>,
+        ),
+        With<Enemy>,
+    >,
+) {
+    for (enemy_ent, enemy_transform, health, loot_table, elite, splits_into) in enemy_query.iter() {
+        if **health > 0 {
+            continue;
+        }
+
+        let position = enemy_transform.translation.truncate();
+
+        commands.entity(enemy_ent).despawn_recursive();
+        enemy_killed.send(EnemyKilled {
+            position,
+            loot_table: loot_table.cloned().unwrap_or_default(),
+            elite: elite.cloned(),
+        });
+
+        if let Some(splits_into) = splits_into {
+            for _ in 0..splits_into.count {
+                let angle = random::<f32>() * 2.0 * PI;
+                let scatter = Vec2::X.rotated_by(angle) * random::<f32>() * SPLIT_SCATTER;
+
+                spawn_enemy(
+                    &mut commands,
+                    &splits_into.enemy_kind,
+                    (position + scatter).extend(enemy_transform.translation.z),
+                    AiState::Chase,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use rand::{distributions::WeightedIndex, prelude::Distribution};
+use serde::Deserialize;
+
+use crate::{
+    ai::AiProfile,
+    components::{EnemyBehaviour, LeashPolicy, OnDeath},
+    elites::Affix,
+    loot::LootTable,
+    status_effects::StatusEffect,
+};
+
+// Constants
+const ENEMY_KINDS_PATH: &str = "data/enemies.kinds.ron";
+
+const SPLIT_SIZE_FACTOR: f32 = 0.7;
+const SPLIT_SPEED_FACTOR: f32 = 1.3;
+const SPLIT_HEALTH_FACTOR: f32 = 0.5;
+
+// Assets
+#[derive(Deref, Deserialize, TypeUuid)]
+#[uuid = "1449404a-1b7b-445b-85aa-62491721cf38"]
+pub struct EnemyKinds(Vec<EnemyKind>);
+
+impl EnemyKinds {
+    /// The kinds shipped in `assets/data/enemies.kinds.ron`, read without going through the asset
+    /// server, e.g. for tests and benchmarks.
+    pub fn bundled() -> Self {
+        ron::de::from_str(include_str!("../assets/data/enemies.kinds.ron"))
+            .expect("Failed to parse the bundled enemy kinds")
+    }
+
+    /// Picks a random kind whose `threat` fits into `threat_budget`, using each kind's `weight` as
+    /// its relative spawn chance.
+    pub fn choose(&self, threat_budget: u32) -> Option<&EnemyKind> {
+        let weights = self.iter().map(|kind| {
+            if kind.threat <= threat_budget {
+                kind.weight
+            } else {
+                0
+            }
+        });
+        let index = WeightedIndex::new(weights).ok()?;
+
+        self.get(index.sample(&mut rand::thread_rng()))
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct EnemyKind {
+    pub name: String,
+    pub weight: u32,
+    pub threat: u32,
+    pub size: f32,
+    pub color: Color,
+    pub health: i32,
+    pub speed: f32,
+    pub acceleration: f32,
+    /// Radians per second
+    pub turn_rate: f32,
+    pub mass: f32,
+    pub contact_damage: i32,
+    /// How far beyond touching the player the contact attack reaches
+    pub contact_range: f32,
+    /// Seconds between two contact attacks of the same enemy
+    pub contact_cooldown: f32,
+    pub behaviour: EnemyBehaviour,
+    #[serde(default)]
+    pub ai: AiProfile,
+    #[serde(default)]
+    pub leash: LeashPolicy,
+    #[serde(default)]
+    pub on_death: Option<OnDeath>,
+    /// Inflicted on the player by this kind's contact damage, explosion or projectiles
+    #[serde(default)]
+    pub on_hit: Option<StatusEffect>,
+    #[serde(default)]
+    pub loot: LootTable,
+    /// Makes every enemy of this kind an elite; the wave director also rolls these at random
+    #[serde(default)]
+    pub affixes: Vec<Affix>,
+}
+
+impl EnemyKind {
+    /// The kind this one splits into on death, along with how many of them, if it has any
+    /// generations left to split.
+    pub fn split(&self) -> Option<(EnemyKind, u32)> {
+        let (children, generations) = match self.on_death {
+            Some(OnDeath::Split {
+                children,
+                generations,
+            }) if generations > 0 => (children, generations),
+            _ => return None,
+        };
+
+        let mut child = self.clone();
+        child.size *= SPLIT_SIZE_FACTOR;
+        child.speed *= SPLIT_SPEED_FACTOR;
+        child.health = ((self.health as f32 * SPLIT_HEALTH_FACTOR) as i32).max(1);
+        child.on_death = Some(OnDeath::Split {
+            children,
+            generations: generations - 1,
+        });
+        // Children of elites are regular enemies
+        child.affixes.clear();
+
+        Some((child, children))
+    }
+}
+
+// Resources
+#[derive(Deref)]
+pub struct EnemyKindsHandle(Handle<EnemyKinds>);
+
+// Loader
+#[derive(Default)]
+struct EnemyKindsLoader;
+
+impl AssetLoader for EnemyKindsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let enemy_kinds: EnemyKinds = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(enemy_kinds));
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["kinds.ron"]
+    }
+}
+
+// Plugin
+pub struct EnemyKindPlugin;
+
+impl Plugin for EnemyKindPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<EnemyKinds>()
+            .init_asset_loader::<EnemyKindsLoader>()
+            .add_startup_system(load_enemy_kinds);
+    }
+}
+
+// Systems
+fn load_enemy_kinds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(EnemyKindsHandle(asset_server.load(ENEMY_KINDS_PATH)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enemy_kinds_asset_parses() {
+        let enemy_kinds = EnemyKinds::bundled();
+
+        assert!(!enemy_kinds.is_empty());
+        assert!(enemy_kinds.choose(u32::MAX).is_some());
+    }
+
+    #[test]
+    fn enemy_kinds_respect_threat_budget() {
+        let enemy_kinds = EnemyKinds::bundled();
+        let cheapest = enemy_kinds.iter().map(|kind| kind.threat).min().unwrap();
+
+        assert!(enemy_kinds.choose(cheapest - 1).is_none());
+
+        for _ in 0..100 {
+            assert!(enemy_kinds.choose(cheapest).unwrap().threat <= cheapest);
+        }
+    }
+
+    #[test]
+    fn splitting_stops_at_generation_limit() {
+        let enemy_kinds = EnemyKinds::bundled();
+        let splitter = enemy_kinds
+            .iter()
+            .find(|kind| kind.on_death.is_some())
+            .unwrap();
+
+        let mut generations = 0;
+        let mut kind = splitter.clone();
+
+        while let Some((child, children)) = kind.split() {
+            assert!(children > 0);
+            assert!(child.size < kind.size);
+            assert!(child.speed > kind.speed);
+            assert!(child.health <= kind.health);
+
+            generations += 1;
+            kind = child;
+        }
+
+        assert!(generations > 0 && generations < 10);
+    }
+}
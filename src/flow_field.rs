@@ -0,0 +1,254 @@
+use std::{cmp::Ordering, collections::BinaryHeap, f32::consts::SQRT_2};
+
+use bevy::prelude::*;
+
+use crate::{
+    app_state::AppState,
+    arena::ARENA_HALF_EXTENTS,
+    components::{HitBox, Obstacle, Player},
+    player::player_movement,
+};
+
+// Constants
+const CELL_SIZE: f32 = 40.0;
+
+// Resources
+/// Grid covering the arena in which every cell points towards the cheapest way to the player.
+pub struct FlowField {
+    origin: Vec2,
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+    directions: Vec<Option<Vec2>>,
+    target: Option<usize>,
+}
+
+impl FlowField {
+    pub fn new(half_extents: Vec2) -> Self {
+        let width = (half_extents.x * 2.0 / CELL_SIZE).ceil() as usize;
+        let height = (half_extents.y * 2.0 / CELL_SIZE).ceil() as usize;
+
+        Self {
+            origin: -half_extents,
+            width,
+            height,
+            blocked: vec![false; width * height],
+            directions: vec![None; width * height],
+            target: None,
+        }
+    }
+
+    fn cell_of(&self, position: Vec2) -> Option<usize> {
+        let local = (position - self.origin) / CELL_SIZE;
+
+        if local.x < 0.0 || local.y < 0.0 {
+            return None;
+        }
+
+        let (x, y) = (local.x as usize, local.y as usize);
+
+        (x < self.width && y < self.height).then(|| y * self.width + x)
+    }
+
+    /// Marks every cell touched by the given box as impassable.
+    pub fn block(&mut self, center: Vec2, size: Vec2) {
+        let min = ((center - size / 2.0 - self.origin) / CELL_SIZE)
+            .floor()
+            .max(Vec2::ZERO);
+        let max = ((center + size / 2.0 - self.origin) / CELL_SIZE).ceil();
+
+        for y in min.y as usize..(max.y as usize).min(self.height) {
+            for x in min.x as usize..(max.x as usize).min(self.width) {
+                self.blocked[y * self.width + x] = true;
+            }
+        }
+
+        self.target = None;
+    }
+
+    /// Runs Dijkstra outwards from the target's cell and points every reachable cell at its
+    /// cheapest neighbour. Does nothing while the target stays in the same cell.
+    pub fn recompute(&mut self, target: Vec2) {
+        let target = match self.cell_of(target) {
+            Some(target) => target,
+            None => return,
+        };
+
+        if self.target == Some(target) {
+            return;
+        }
+
+        let mut costs = vec![f32::INFINITY; self.width * self.height];
+        let mut frontier = BinaryHeap::new();
+
+        costs[target] = 0.0;
+        frontier.push(Frontier(0.0, target));
+
+        while let Some(Frontier(cost, cell)) = frontier.pop() {
+            if cost > costs[cell] {
+                continue;
+            }
+
+            for (neighbour, step) in self.neighbours(cell) {
+                if cost + step < costs[neighbour] {
+                    costs[neighbour] = cost + step;
+                    frontier.push(Frontier(cost + step, neighbour));
+                }
+            }
+        }
+
+        for cell in 0..costs.len() {
+            self.directions[cell] = self
+                .neighbours(cell)
+                .map(|(neighbour, _)| neighbour)
+                .filter(|neighbour| costs[*neighbour] < costs[cell])
+                .min_by(|a, b| costs[*a].total_cmp(&costs[*b]))
+                .map(|neighbour| (self.center_of(neighbour) - self.center_of(cell)).normalize());
+        }
+
+        self.target = Some(target);
+    }
+
+    /// Direction to steer in from `position`, or `None` when there is no path or the position
+    /// already shares a cell with the target.
+    pub fn direction_at(&self, position: Vec2) -> Option<Vec2> {
+        self.directions[self.cell_of(position)?]
+    }
+
+    fn center_of(&self, cell: usize) -> Vec2 {
+        let x = (cell % self.width) as f32;
+        let y = (cell / self.width) as f32;
+
+        self.origin + (Vec2::new(x, y) + 0.5) * CELL_SIZE
+    }
+
+    /// Walkable neighbours including diagonals, which are only allowed if they do not cut a
+    /// blocked corner.
+    fn neighbours(&self, cell: usize) -> impl Iterator<Item = (usize, f32)> + '_ {
+        let (x, y) = ((cell % self.width) as isize, (cell / self.width) as isize);
+
+        [
+            (-1, 0),
+            (1, 0),
+            (0, -1),
+            (0, 1),
+            (-1, -1),
+            (-1, 1),
+            (1, -1),
+            (1, 1),
+        ]
+        .into_iter()
+        .filter_map(move |(dx, dy)| {
+            let walkable = |x: isize, y: isize| {
+                x >= 0
+                    && y >= 0
+                    && (x as usize) < self.width
+                    && (y as usize) < self.height
+                    && !self.blocked[y as usize * self.width + x as usize]
+            };
+
+            if !walkable(x + dx, y + dy) {
+                return None;
+            }
+
+            if dx != 0 && dy != 0 {
+                if !walkable(x + dx, y) || !walkable(x, y + dy) {
+                    return None;
+                }
+
+                return Some(((y + dy) as usize * self.width + (x + dx) as usize, SQRT_2));
+            }
+
+            Some(((y + dy) as usize * self.width + (x + dx) as usize, 1.0))
+        })
+    }
+}
+
+/// Min-heap entry for Dijkstra's frontier.
+struct Frontier(f32, usize);
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0)
+    }
+}
+
+// Plugin
+pub struct FlowFieldPlugin;
+
+impl Plugin for FlowFieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(update_flow_field.after(player_movement)),
+        )
+        .insert_resource(FlowField::new(ARENA_HALF_EXTENTS));
+    }
+}
+
+// Systems
+pub fn update_flow_field(
+    mut flow_field: ResMut<FlowField>,
+    player_transform: Query<&Transform, With<Player>>,
+    new_obstacles: Query<(&Transform, &HitBox), Added<Obstacle>>,
+) {
+    for (obstacle_transform, obstacle_hit_box) in new_obstacles.iter() {
+        flow_field.block(
+            obstacle_transform.translation.truncate(),
+            **obstacle_hit_box,
+        );
+    }
+
+    flow_field.recompute(player_transform.single().translation.truncate());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_field_points_at_target() {
+        let mut flow_field = FlowField::new(Vec2::new(200.0, 200.0));
+        flow_field.recompute(Vec2::ZERO);
+
+        let direction = flow_field.direction_at(Vec2::new(150.0, 10.0)).unwrap();
+
+        assert!(direction.x < 0.0);
+        assert!(flow_field.direction_at(Vec2::new(10.0, 10.0)).is_none());
+    }
+
+    #[test]
+    fn flow_field_routes_around_walls() {
+        let mut flow_field = FlowField::new(Vec2::new(200.0, 200.0));
+        // Vertical wall between the target and the sample point, open at the top
+        flow_field.block(Vec2::new(60.0, -40.0), Vec2::new(40.0, 320.0));
+        flow_field.recompute(Vec2::new(0.0, -100.0));
+
+        let direction = flow_field.direction_at(Vec2::new(140.0, -100.0)).unwrap();
+
+        assert!(direction.y > 0.0);
+    }
+
+    #[test]
+    fn enclosed_cells_have_no_direction() {
+        let mut flow_field = FlowField::new(Vec2::new(200.0, 200.0));
+        flow_field.block(Vec2::new(0.0, 0.0), Vec2::new(400.0, 40.0));
+        flow_field.recompute(Vec2::new(0.0, -100.0));
+
+        assert!(flow_field.direction_at(Vec2::new(0.0, 100.0)).is_none());
+    }
+}
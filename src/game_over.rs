@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::Action,
+    app_state::{despawn_all, AppState},
+    components::{GameOverScreen, Health, Player},
+    enemies::EnemyKilled,
+    loot::Currency,
+    menus::spawn_screen,
+    waves::WaveStarted,
+};
+
+// Constants
+const DEATH_SEQUENCE_TIME: f32 = 1.5;
+// Radians per second the player spins while going down
+const DEATH_SPIN_SPEED: f32 = 4.0 * std::f32::consts::PI;
+
+const TEXT_COLOR: Color = Color::WHITE;
+const TITLE_COLOR: Color = Color::CRIMSON;
+const BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.7);
+
+// Resources
+/// What the current run has achieved so far, shown on the game-over screen.
+#[derive(Default)]
+pub struct RunStats {
+    pub time_survived: f32,
+    pub wave: u32,
+    pub kills: u32,
+}
+
+#[derive(Deref, DerefMut)]
+struct DeathSequenceTimer(Timer);
+
+// Plugin
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(track_run_stats)
+                .with_system(player_death),
+        )
+        .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(start_death_sequence))
+        .add_system_set(
+            SystemSet::on_update(AppState::GameOver)
+                .with_system(play_death_sequence)
+                .with_system(leave_game_over),
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::GameOver)
+                .with_system(despawn_all::<GameOverScreen>)
+                .with_system(reset_run_stats),
+        )
+        .init_resource::<RunStats>()
+        .insert_resource(DeathSequenceTimer(Timer::from_seconds(
+            DEATH_SEQUENCE_TIME,
+            false,
+        )));
+    }
+}
+
+// Systems
+fn track_run_stats(
+    time: Res<Time>,
+    mut run_stats: ResMut<RunStats>,
+    mut wave_started: EventReader<WaveStarted>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+) {
+    run_stats.time_survived += time.delta_seconds();
+    run_stats.kills += enemy_killed.iter().count() as u32;
+
+    if let Some(WaveStarted(wave)) = wave_started.iter().last() {
+        run_stats.wave = *wave;
+    }
+}
+
+pub fn player_death(
+    mut app_state: ResMut<State<AppState>>,
+    mut player_health: Query<&mut Health, With<Player>>,
+) {
+    let mut player_health = player_health.single_mut();
+
+    if **player_health <= 0 {
+        **player_health = 0;
+        app_state.set(AppState::GameOver).unwrap();
+    }
+}
+
+fn start_death_sequence(mut death_sequence_timer: ResMut<DeathSequenceTimer>) {
+    death_sequence_timer.reset();
+}
+
+/// Spins the player out of sight, then shows how the run went.
+fn play_death_sequence(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    run_stats: Res<RunStats>,
+    currency: Res<Currency>,
+    mut death_sequence_timer: ResMut<DeathSequenceTimer>,
+    mut player_query: Query<(&mut Transform, &mut Sprite), With<Player>>,
+) {
+    if death_sequence_timer.finished() {
+        return;
+    }
+
+    death_sequence_timer.tick(time.delta());
+
+    let (mut player_transform, mut player_sprite) = player_query.single_mut();
+    player_transform.rotate(Quat::from_rotation_z(
+        DEATH_SPIN_SPEED * time.delta_seconds(),
+    ));
+    player_sprite
+        .color
+        .set_a(death_sequence_timer.percent_left());
+
+    if death_sequence_timer.just_finished() {
+        spawn_game_over_screen(&mut commands, &asset_server, &run_stats, **currency);
+    }
+}
+
+fn leave_game_over(
+    actions: Res<Input<Action>>,
+    mut app_state: ResMut<State<AppState>>,
+    game_over_screen: Query<(), With<GameOverScreen>>,
+) {
+    // Only once the screen is up, so the run is not left by accident while dying
+    if game_over_screen.is_empty() {
+        return;
+    }
+
+    if actions.just_pressed(Action::Confirm) {
+        app_state.set(AppState::Playing).unwrap();
+    } else if actions.just_pressed(Action::Back) {
+        app_state.set(AppState::MainMenu).unwrap();
+    }
+}
+
+fn reset_run_stats(mut run_stats: ResMut<RunStats>) {
+    *run_stats = RunStats::default();
+}
+
+fn spawn_game_over_screen(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    run_stats: &RunStats,
+    currency: u32,
+) {
+    let time_survived = run_stats.time_survived as u32;
+
+    spawn_screen(
+        commands,
+        asset_server,
+        BACKGROUND_COLOR,
+        vec![
+            ("You died".to_string(), 60.0, TITLE_COLOR),
+            (
+                format!("Survived {}:{:02}", time_survived / 60, time_survived % 60),
+                30.0,
+                TEXT_COLOR,
+            ),
+            (format!("Reached wave {}", run_stats.wave), 30.0, TEXT_COLOR),
+            (
+                format!("Enemies killed: {}", run_stats.kills),
+                30.0,
+                TEXT_COLOR,
+            ),
+            (format!("Currency: {}", currency), 30.0, TEXT_COLOR),
+            (
+                "Press Enter to restart or Escape for the main menu".to_string(),
+                24.0,
+                TEXT_COLOR,
+            ),
+        ],
+    )
+    .insert(GameOverScreen)
+    .insert(Name::new("GameOverScreen"));
+}
@@ -0,0 +1,168 @@
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    app_state::AppState,
+    components::Player,
+    mouse::{update_cursor_position, MousePosition},
+    util::VectorMath,
+};
+
+// Constants
+// Share of the stick's travel that is ignored, measured from the center
+const MOVE_DEADZONE: f32 = 0.15;
+// Larger than for moving, so letting go of the stick does not swing the aim around
+const AIM_DEADZONE: f32 = 0.3;
+
+// Resources
+/// The device the player aimed with last. Shots and rolls follow it until the other one is used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AimDevice {
+    #[default]
+    Mouse,
+    Gamepad,
+}
+
+/// Unit vector the player aims along, or zero while there is nothing to aim at.
+#[derive(Default, Deref, DerefMut)]
+pub struct AimDirection(Vec2);
+
+// System params
+/// Sticks of the first connected gamepad, which read as idle without one. Buttons are bound to
+/// actions instead.
+#[derive(SystemParam)]
+pub struct GamepadInput<'w, 's> {
+    gamepads: Res<'w, Gamepads>,
+    buttons: Res<'w, Input<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    #[system_param(ignore)]
+    _marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> GamepadInput<'w, 's> {
+    /// Left stick, scaled so a half-tilted stick moves at half speed.
+    pub fn movement(&self) -> Vec2 {
+        apply_deadzone(
+            self.stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY),
+            MOVE_DEADZONE,
+        )
+    }
+
+    /// Direction of the right stick, if it is tilted far enough to aim.
+    pub fn aim(&self) -> Option<Vec2> {
+        let aim = apply_deadzone(
+            self.stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY),
+            AIM_DEADZONE,
+        );
+
+        (aim != Vec2::ZERO).then(|| aim.normalize())
+    }
+
+    /// Whether any button of any gamepad was pressed this frame.
+    pub fn any_button_just_pressed(&self) -> bool {
+        self.buttons.get_just_pressed().next().is_some()
+    }
+
+    fn gamepad(&self) -> Option<Gamepad> {
+        self.gamepads.iter().next().copied()
+    }
+
+    fn stick(&self, x_axis: GamepadAxisType, y_axis: GamepadAxisType) -> Vec2 {
+        self.gamepad().map_or(Vec2::ZERO, |gamepad| {
+            Vec2::new(
+                self.axes.get(GamepadAxis(gamepad, x_axis)).unwrap_or(0.0),
+                self.axes.get(GamepadAxis(gamepad, y_axis)).unwrap_or(0.0),
+            )
+        })
+    }
+}
+
+// Plugin
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(update_aim.after(update_cursor_position)),
+        )
+        .insert_resource(AimDevice::default())
+        .insert_resource(AimDirection::default());
+    }
+}
+
+/// Ignores stick tilts within `deadzone` of the center and rescales the rest, so the output still
+/// ranges from zero to one without jumping at the edge of the deadzone.
+pub fn apply_deadzone(stick: Vec2, deadzone: f32) -> Vec2 {
+    let magnitude = stick.length();
+
+    if magnitude <= deadzone {
+        return Vec2::ZERO;
+    }
+
+    let scaled_magnitude = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0);
+
+    stick / magnitude * scaled_magnitude
+}
+
+// Systems
+/// Switches to whichever device was used last and points the aim along it. A released right stick
+/// keeps the last direction, like twin-stick shooters do.
+pub fn update_aim(
+    gamepad_input: GamepadInput,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mouse_position: Res<MousePosition>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    mut aim_device: ResMut<AimDevice>,
+    mut aim_direction: ResMut<AimDirection>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let mouse_used =
+        cursor_moved.iter().count() > 0 || mouse_buttons.get_just_pressed().next().is_some();
+    let gamepad_aim = gamepad_input.aim();
+
+    if gamepad_aim.is_some() || gamepad_input.any_button_just_pressed() {
+        *aim_device = AimDevice::Gamepad;
+    } else if mouse_used {
+        *aim_device = AimDevice::Mouse;
+    }
+
+    match *aim_device {
+        AimDevice::Mouse => {
+            let player_position = player_query.single().translation.truncate();
+            **aim_direction = player_position.scaled_vector_to(&mouse_position, 1.0);
+        }
+        AimDevice::Gamepad => {
+            if let Some(gamepad_aim) = gamepad_aim {
+                **aim_direction = gamepad_aim;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_tilts_are_ignored() {
+        assert_eq!(apply_deadzone(Vec2::new(0.1, -0.1), 0.15), Vec2::ZERO);
+    }
+
+    #[test]
+    fn tilts_are_rescaled_past_the_deadzone() {
+        let stick = apply_deadzone(Vec2::new(0.0, 0.6), 0.2);
+
+        assert!((stick.y - 0.5).abs() < 1e-6);
+        assert_eq!(stick.x, 0.0);
+    }
+
+    #[test]
+    fn full_tilts_do_not_exceed_one() {
+        // Corners of square gates report more than one
+        let stick = apply_deadzone(Vec2::new(1.0, 1.0), 0.15);
+
+        assert!((stick.length() - 1.0).abs() < 1e-6);
+    }
+}
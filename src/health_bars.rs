@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+
+use crate::{
+    app_state::AppState,
+    components::{Enemy, Health, HealthBar, HealthBarFill, HealthBarTimer, MaxHealth},
+};
+
+// Constants
+const HEALTH_BAR_HEIGHT: f32 = 5.0;
+const HEALTH_BAR_OFFSET: f32 = 8.0;
+const HEALTH_BAR_COLOR: Color = Color::LIME_GREEN;
+const HEALTH_BAR_BACKGROUND_COLOR: Color = Color::BLACK;
+
+const HEALTH_BAR_DISPLAY_TIME: f32 = 3.0;
+const HEALTH_BAR_FADE_TIME: f32 = 1.0;
+
+// Plugin
+pub struct HealthBarPlugin;
+
+impl Plugin for HealthBarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(show_health_bars)
+                .with_system(fade_health_bars.after(show_health_bars)),
+        );
+    }
+}
+
+/// Spawns a hidden health bar above an enemy of the given size, which shows up once it takes
+/// damage.
+pub fn spawn_health_bar(parent: &mut ChildBuilder, enemy_size: Vec2) {
+    let size = Vec2::new(enemy_size.x, HEALTH_BAR_HEIGHT);
+
+    parent
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: HEALTH_BAR_BACKGROUND_COLOR,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, enemy_size.y / 2.0 + HEALTH_BAR_OFFSET, 1.0),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(HealthBar)
+        .insert(Name::new("HealthBar"))
+        .insert(HealthBarTimer(Timer::from_seconds(
+            HEALTH_BAR_DISPLAY_TIME,
+            false,
+        )))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: HEALTH_BAR_COLOR,
+                        custom_size: Some(size),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 0.0, 1.0),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(HealthBarFill);
+        });
+}
+
+// Systems
+fn show_health_bars(
+    enemy_query: Query<(&Health, &MaxHealth, &Children), (With<Enemy>, Changed<Health>)>,
+    mut health_bar_query: Query<
+        (&mut HealthBarTimer, &Sprite, &mut Visibility, &Children),
+        With<HealthBar>,
+    >,
+    mut health_bar_fill_query: Query<
+        (&mut Sprite, &mut Transform, &mut Visibility),
+        (With<HealthBarFill>, Without<HealthBar>),
+    >,
+) {
+    for (health, max_health, children) in enemy_query.iter() {
+        // Freshly spawned enemies also count as changed, but only hurt ones get a bar
+        if **health >= **max_health {
+            continue;
+        }
+
+        for child in children.iter() {
+            if let Ok((
+                mut health_bar_timer,
+                health_bar_sprite,
+                mut health_bar_visibility,
+                health_bar_children,
+            )) = health_bar_query.get_mut(*child)
+            {
+                health_bar_timer.reset();
+                // Sprite visibility is not inherited, so the fill is toggled separately
+                health_bar_visibility.is_visible = true;
+
+                let width = health_bar_sprite.custom_size.unwrap_or_default().x;
+                let fill = (**health).max(0) as f32 / **max_health as f32;
+
+                for fill_child in health_bar_children.iter() {
+                    if let Ok((mut fill_sprite, mut fill_transform, mut fill_visibility)) =
+                        health_bar_fill_query.get_mut(*fill_child)
+                    {
+                        fill_visibility.is_visible = true;
+
+                        // Sprites are centred, so shift the shrinking fill to stay left-aligned
+                        fill_sprite.custom_size = Some(Vec2::new(width * fill, HEALTH_BAR_HEIGHT));
+                        fill_transform.translation.x = -width * (1.0 - fill) / 2.0;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn fade_health_bars(
+    time: Res<Time>,
+    mut health_bar_query: Query<
+        (&mut HealthBarTimer, &mut Sprite, &mut Visibility, &Children),
+        With<HealthBar>,
+    >,
+    mut health_bar_fill_query: Query<
+        (&mut Sprite, &mut Visibility),
+        (With<HealthBarFill>, Without<HealthBar>),
+    >,
+) {
+    for (mut health_bar_timer, mut health_bar_sprite, mut health_bar_visibility, children) in
+        health_bar_query.iter_mut()
+    {
+        if !health_bar_visibility.is_visible {
+            continue;
+        }
+
+        health_bar_timer.tick(time.delta());
+
+        let remaining = health_bar_timer.duration().as_secs_f32() - health_bar_timer.elapsed_secs();
+        let alpha = (remaining / HEALTH_BAR_FADE_TIME).clamp(0.0, 1.0);
+        let visible = !health_bar_timer.finished();
+
+        health_bar_sprite.color.set_a(alpha);
+        health_bar_visibility.is_visible = visible;
+
+        for child in children.iter() {
+            if let Ok((mut fill_sprite, mut fill_visibility)) =
+                health_bar_fill_query.get_mut(*child)
+            {
+                fill_sprite.color.set_a(alpha);
+                fill_visibility.is_visible = visible;
+            }
+        }
+    }
+}
@@ -0,0 +1,33 @@
+// Systems take their data as arguments and queries, which these lints flag all over, and bevy's
+// `Bundle` derive forgets plain fields by design
+#![allow(
+    clippy::too_many_arguments,
+    clippy::type_complexity,
+    clippy::forget_non_drop
+)]
+
+pub mod actions;
+pub mod ai;
+pub mod app_state;
+pub mod arena;
+pub mod bosses;
+pub mod components;
+pub mod elites;
+pub mod enemies;
+pub mod enemy_kinds;
+pub mod flow_field;
+pub mod game_over;
+pub mod gamepad;
+pub mod health_bars;
+pub mod loot;
+pub mod menus;
+pub mod mouse;
+pub mod player;
+pub mod ranged;
+pub mod spatial_grid;
+pub mod spawning;
+pub mod status_effects;
+pub mod ui;
+pub mod util;
+pub mod waves;
+pub mod weapons;
@@ -0,0 +1,306 @@
+use std::f32::consts::PI;
+
+use bevy::{prelude::*, sprite::collide_aabb::collide};
+use rand::{
+    distributions::WeightedIndex,
+    prelude::{Distribution, StdRng},
+    Rng, SeedableRng,
+};
+use serde::Deserialize;
+
+use crate::{
+    app_state::{despawn_all, AppState},
+    components::{
+        CurrentAmmo, Health, HitBox, LifetimeTimer, MaxHealth, MaximumAmmo, Player, SpeedBoost,
+    },
+    enemies::EnemyKilled,
+    util::VectorMath,
+    weapons::SelectedWeapon,
+};
+
+// Constants
+const PICKUP_SIZE: f32 = 12.0;
+const PICKUP_LIFETIME: f32 = 20.0;
+const PICKUP_SCATTER: f32 = 20.0;
+
+// Set to make loot drops reproducible between runs
+const LOOT_SEED: Option<u64> = None;
+
+// Loot tables
+/// Rolled `rolls` times when an enemy dies, every roll picking one entry by weight.
+#[derive(Clone, Component, Default, Deserialize)]
+pub struct LootTable {
+    pub rolls: u32,
+    pub entries: Vec<LootEntry>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct LootEntry {
+    pub weight: u32,
+    /// `None` makes the roll drop nothing
+    pub drop: Option<Loot>,
+}
+
+#[derive(Clone, Copy, Component, Debug, Deserialize, PartialEq)]
+pub enum Loot {
+    Health(i32),
+    Ammo(u32),
+    PowerUp {
+        speed_multiplier: f32,
+        duration: f32,
+    },
+    Currency(u32),
+}
+
+impl LootTable {
+    pub fn roll(&self, rng: &mut impl Rng) -> Vec<Loot> {
+        let index = match WeightedIndex::new(self.entries.iter().map(|entry| entry.weight)) {
+            Ok(index) => index,
+            Err(_) => return Vec::new(),
+        };
+
+        (0..self.rolls)
+            .filter_map(|_| self.entries[index.sample(rng)].drop)
+            .collect()
+    }
+}
+
+impl Loot {
+    fn color(&self) -> Color {
+        match self {
+            Loot::Health(_) => Color::GREEN,
+            Loot::Ammo(_) => Color::YELLOW,
+            Loot::PowerUp { .. } => Color::CYAN,
+            Loot::Currency(_) => Color::GOLD,
+        }
+    }
+}
+
+// Resources
+/// Source of randomness for loot rolls, seeded from `LOOT_SEED` when it is set.
+#[derive(Deref, DerefMut)]
+pub struct LootRng(StdRng);
+
+impl LootRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl Default for LootRng {
+    fn default() -> Self {
+        match LOOT_SEED {
+            Some(seed) => Self::from_seed(seed),
+            None => Self(StdRng::from_entropy()),
+        }
+    }
+}
+
+#[derive(Default, Deref, DerefMut)]
+pub struct Currency(u32);
+
+// Plugin
+pub struct LootPlugin;
+
+impl Plugin for LootPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(drop_loot)
+                .with_system(collect_loot)
+                .with_system(expire_loot)
+                .with_system(tick_speed_boosts),
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::GameOver)
+                .with_system(despawn_all::<Loot>)
+                .with_system(reset_currency),
+        )
+        .init_resource::<LootRng>()
+        .init_resource::<Currency>();
+    }
+}
+
+// Systems
+fn reset_currency(mut currency: ResMut<Currency>) {
+    **currency = 0;
+}
+
+fn drop_loot(
+    mut commands: Commands,
+    mut loot_rng: ResMut<LootRng>,
+    mut enemy_killed: EventReader<EnemyKilled>,
+) {
+    for EnemyKilled {
+        position,
+        loot_table,
+        ..
+    } in enemy_killed.iter()
+    {
+        for loot in loot_table.roll(&mut **loot_rng) {
+            let angle = loot_rng.gen::<f32>() * 2.0 * PI;
+            let scatter = Vec2::X.rotated_by(angle) * loot_rng.gen::<f32>() * PICKUP_SCATTER;
+            let size = Vec2::splat(PICKUP_SIZE);
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: loot.color(),
+                        custom_size: Some(size),
+                        ..default()
+                    },
+                    transform: Transform::from_translation((*position + scatter).extend(0.0)),
+                    ..default()
+                })
+                .insert(loot)
+                .insert(Name::new("Loot"))
+                .insert(HitBox(size))
+                .insert(LifetimeTimer(Timer::from_seconds(PICKUP_LIFETIME, false)));
+        }
+    }
+}
+
+fn collect_loot(
+    mut commands: Commands,
+    selected_weapon: Res<SelectedWeapon>,
+    mut currency: ResMut<Currency>,
+    mut player_query: Query<(Entity, &Transform, &HitBox, &mut Health, &MaxHealth), With<Player>>,
+    mut weapon_query: Query<(&mut CurrentAmmo, &MaximumAmmo)>,
+    loot_query: Query<(Entity, &Transform, &HitBox, &Loot)>,
+) {
+    let (player_ent, player_transform, player_hit_box, mut player_health, player_max_health) =
+        player_query.single_mut();
+
+    for (loot_ent, loot_transform, loot_hit_box, loot) in loot_query.iter() {
+        if collide(
+            player_transform.translation,
+            **player_hit_box,
+            loot_transform.translation,
+            **loot_hit_box,
+        )
+        .is_none()
+        {
+            continue;
+        }
+
+        match *loot {
+            Loot::Health(amount) => {
+                **player_health = (**player_health + amount).min(**player_max_health);
+            }
+            Loot::Ammo(amount) => {
+                if let Some(weapon_ent) = **selected_weapon {
+                    if let Ok((mut current_ammo, maximum_ammo)) = weapon_query.get_mut(weapon_ent) {
+                        **current_ammo = (**current_ammo + amount).min(**maximum_ammo);
+                    }
+                }
+            }
+            Loot::PowerUp {
+                speed_multiplier,
+                duration,
+            } => {
+                commands.entity(player_ent).insert(SpeedBoost {
+                    multiplier: speed_multiplier,
+                    timer: Timer::from_seconds(duration, false),
+                });
+            }
+            Loot::Currency(amount) => {
+                **currency += amount;
+            }
+        }
+
+        commands.entity(loot_ent).despawn();
+    }
+}
+
+fn expire_loot(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut loot_query: Query<(Entity, &mut LifetimeTimer), With<Loot>>,
+) {
+    for (loot_ent, mut lifetime_timer) in loot_query.iter_mut() {
+        lifetime_timer.tick(time.delta());
+
+        if lifetime_timer.finished() {
+            commands.entity(loot_ent).despawn();
+        }
+    }
+}
+
+fn tick_speed_boosts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut boost_query: Query<(Entity, &mut SpeedBoost)>,
+) {
+    for (boosted_ent, mut speed_boost) in boost_query.iter_mut() {
+        speed_boost.timer.tick(time.delta());
+
+        if speed_boost.timer.finished() {
+            commands.entity(boosted_ent).remove::<SpeedBoost>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loot_table() -> LootTable {
+        LootTable {
+            rolls: 20,
+            entries: vec![
+                LootEntry {
+                    weight: 1,
+                    drop: Some(Loot::Currency(1)),
+                },
+                LootEntry {
+                    weight: 1,
+                    drop: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn seeded_rolls_are_reproducible() {
+        let loot_table = LootTable {
+            rolls: 10,
+            entries: vec![
+                LootEntry {
+                    weight: 1,
+                    drop: Some(Loot::Currency(1)),
+                },
+                LootEntry {
+                    weight: 1,
+                    drop: Some(Loot::Health(1)),
+                },
+                LootEntry {
+                    weight: 1,
+                    drop: Some(Loot::Ammo(10)),
+                },
+                LootEntry {
+                    weight: 1,
+                    drop: None,
+                },
+            ],
+        };
+        let rolls = |seed| {
+            let mut loot_rng = LootRng::from_seed(seed);
+
+            (0..5)
+                .map(|_| loot_table.roll(&mut *loot_rng))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(rolls(42), rolls(42));
+        assert_ne!(rolls(42), rolls(43));
+    }
+
+    #[test]
+    fn empty_entries_drop_nothing() {
+        let mut loot_rng = LootRng::from_seed(0);
+        let rolls = loot_table().roll(&mut *loot_rng).len();
+
+        assert!(rolls < 20);
+        assert!(LootTable::default().roll(&mut *loot_rng).is_empty());
+    }
+}
@@ -1,21 +1,33 @@
-mod components;
-mod enemies;
-mod mouse;
-mod player;
-mod ui;
-mod util;
-mod weapons;
-
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::prelude::*;
 use bevy_inspector_egui::WorldInspectorPlugin;
 
-use components::{InspectionPlugin, MainCamera};
-use enemies::EnemyPlugin;
-use mouse::MousePlugin;
-use player::PlayerPlugin;
-use ui::UiPlugin;
-use weapons::WeaponPlugin;
+use bevy_twinstick::{
+    actions::ActionPlugin,
+    ai::AiPlugin,
+    app_state::AppStatePlugin,
+    arena::ArenaPlugin,
+    bosses::BossPlugin,
+    components::{InspectionPlugin, MainCamera},
+    elites::ElitePlugin,
+    enemies::EnemyPlugin,
+    enemy_kinds::EnemyKindPlugin,
+    flow_field::FlowFieldPlugin,
+    game_over::GameOverPlugin,
+    gamepad::GamepadPlugin,
+    health_bars::HealthBarPlugin,
+    loot::LootPlugin,
+    menus::MenuPlugin,
+    mouse::MousePlugin,
+    player::PlayerPlugin,
+    ranged::RangedPlugin,
+    spatial_grid::SpatialGridPlugin,
+    spawning::SpawningPlugin,
+    status_effects::StatusEffectPlugin,
+    ui::UiPlugin,
+    waves::WavePlugin,
+    weapons::WeaponPlugin,
+};
 
 // TODO:
 // 1. Aiming + weapon damage
@@ -23,8 +35,6 @@ use weapons::WeaponPlugin;
 // 3. Reload UI
 // 4. Weapon models (rectangles) NOTE: What about projectiles?
 // 5. Score
-// 6. Rolling
-// 7. (Monster HP UI)
 fn main() {
     App::new()
         // Resources
@@ -38,13 +48,31 @@ fn main() {
         .add_startup_system(setup_camera)
         // Plugins
         .add_plugins(DefaultPlugins)
+        .add_plugin(ActionPlugin)
+        .add_plugin(AppStatePlugin)
+        .add_plugin(MenuPlugin)
+        .add_plugin(GameOverPlugin)
+        .add_plugin(ArenaPlugin)
         .add_plugin(PlayerPlugin)
         .add_plugin(WeaponPlugin)
+        .add_plugin(EnemyKindPlugin)
+        .add_plugin(FlowFieldPlugin)
+        .add_plugin(SpatialGridPlugin)
         .add_plugin(EnemyPlugin)
+        .add_plugin(AiPlugin)
+        .add_plugin(RangedPlugin)
+        .add_plugin(BossPlugin)
+        .add_plugin(ElitePlugin)
+        .add_plugin(StatusEffectPlugin)
+        .add_plugin(HealthBarPlugin)
+        .add_plugin(LootPlugin)
+        .add_plugin(SpawningPlugin)
+        .add_plugin(WavePlugin)
         .add_plugin(MousePlugin)
+        .add_plugin(GamepadPlugin)
         .add_plugin(UiPlugin)
         .add_plugin(LogDiagnosticsPlugin::default())
-        .add_plugin(FrameTimeDiagnosticsPlugin::default())
+        .add_plugin(FrameTimeDiagnosticsPlugin)
         .add_plugin(WorldInspectorPlugin::new())
         .add_plugin(InspectionPlugin)
         .run();
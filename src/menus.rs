@@ -0,0 +1,135 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+
+use crate::{
+    actions::Action,
+    app_state::{despawn_all, AppState},
+    components::{MainMenuScreen, PauseScreen},
+    game_over::player_death,
+};
+
+// Constants
+const TEXT_COLOR: Color = Color::WHITE;
+const TITLE_COLOR: Color = Color::CRIMSON;
+const MAIN_MENU_BACKGROUND_COLOR: Color = Color::BLACK;
+const PAUSE_BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.5);
+
+// Plugin
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(spawn_main_menu))
+            .add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(start_run))
+            .add_system_set(
+                SystemSet::on_exit(AppState::MainMenu).with_system(despawn_all::<MainMenuScreen>),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Playing).with_system(pause.after(player_death)),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Paused).with_system(spawn_pause_screen))
+            .add_system_set(SystemSet::on_update(AppState::Paused).with_system(resume))
+            .add_system_set(
+                SystemSet::on_exit(AppState::Paused).with_system(despawn_all::<PauseScreen>),
+            );
+    }
+}
+
+/// Full-screen overlay with centered lines of `(text, font size, color)`.
+pub fn spawn_screen<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    asset_server: &AssetServer,
+    background_color: Color,
+    lines: Vec<(String, f32, Color)>,
+) -> EntityCommands<'w, 's, 'a> {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    let mut screen = commands.spawn_bundle(NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        color: background_color.into(),
+        ..default()
+    });
+
+    screen.with_children(|parent| {
+        for (value, font_size, color) in lines {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: Rect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                text: Text::with_section(
+                    value,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size,
+                        color,
+                    },
+                    Default::default(),
+                ),
+                ..default()
+            });
+        }
+    });
+
+    screen
+}
+
+// Systems
+fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_screen(
+        &mut commands,
+        &asset_server,
+        MAIN_MENU_BACKGROUND_COLOR,
+        vec![
+            ("Cthulhu-Strike 1.6".to_string(), 60.0, TITLE_COLOR),
+            ("Press Enter to start".to_string(), 30.0, TEXT_COLOR),
+        ],
+    )
+    .insert(MainMenuScreen)
+    .insert(Name::new("MainMenuScreen"));
+}
+
+fn start_run(actions: Res<Input<Action>>, mut app_state: ResMut<State<AppState>>) {
+    if actions.just_pressed(Action::Confirm) {
+        app_state.set(AppState::Playing).unwrap();
+    }
+}
+
+fn pause(mut actions: ResMut<Input<Action>>, mut app_state: ResMut<State<AppState>>) {
+    if actions.just_pressed(Action::Pause) {
+        // Dying on the same frame already queued the game over, which takes precedence
+        if app_state.push(AppState::Paused).is_err() {
+            return;
+        }
+
+        // The paused state runs within the same frame and would resume right away otherwise
+        actions.clear_just_pressed(Action::Pause);
+    }
+}
+
+fn spawn_pause_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_screen(
+        &mut commands,
+        &asset_server,
+        PAUSE_BACKGROUND_COLOR,
+        vec![
+            ("Paused".to_string(), 60.0, TEXT_COLOR),
+            ("Press Escape to resume".to_string(), 30.0, TEXT_COLOR),
+        ],
+    )
+    .insert(PauseScreen)
+    .insert(Name::new("PauseScreen"));
+}
+
+fn resume(mut actions: ResMut<Input<Action>>, mut app_state: ResMut<State<AppState>>) {
+    if actions.just_pressed(Action::Pause) {
+        app_state.pop().unwrap();
+        actions.clear_just_pressed(Action::Pause);
+    }
+}
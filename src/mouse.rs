@@ -17,7 +17,7 @@ impl Plugin for MousePlugin {
 }
 
 // Slightly modified version of: https://bevy-cheatbook.github.io/cookbook/cursor2world.html
-fn update_cursor_position(
+pub fn update_cursor_position(
     windows: Res<Windows>,
     mut mouse_position: ResMut<MousePosition>,
     main_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
@@ -36,7 +36,7 @@ fn update_cursor_position(
     // check if the cursor is inside the window and get its position
     if let Some(screen_pos) = window.cursor_position() {
         // get the size of the window
-        let window_size = Vec2::new(window.width() as f32, window.height() as f32);
+        let window_size = Vec2::new(window.width(), window.height());
 
         // convert screen position [0..resolution] to ndc [-1..1] (gpu coordinates)
         let ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
@@ -1,29 +1,56 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
 use crate::{
-    components::{Health, InvincibilityTimer, Invincible, MainCamera, Player, Speed},
-    util::VectorMath,
+    actions::Action,
+    app_state::{despawn_all, AppState},
+    components::{
+        Acceleration, BaseColor, Friction, Health, HitBox, InvincibilityTimer, Invincible,
+        MainCamera, MaxHealth, Player, Roll, RollCooldown, Speed, SpeedBoost, Velocity,
+    },
+    gamepad::{update_aim, AimDirection, GamepadInput},
+    status_effects::StatusEffects,
+    util::{finished_timer, VectorMath},
 };
 
 // Constants
 const PLAYER_COLOR: Color = Color::BLUE;
 const PLAYER_SPEED: f32 = 120.0;
+// Reaches full speed in a tenth of a second and stops a little slower than that
+const PLAYER_ACCELERATION: f32 = 1200.0;
+const PLAYER_FRICTION: f32 = 900.0;
 const PLAYER_HEALTH: i32 = 5;
 const PLAYER_INVINCIBILITY_TIME: f32 = 2.0;
 
+const ROLL_DISTANCE: f32 = 150.0;
+const ROLL_TIME: f32 = 0.3;
+// Only the start of the roll is untouchable, so dodging still takes timing
+const ROLL_INVINCIBILITY_TIME: f32 = 0.2;
+const ROLL_COOLDOWN: f32 = 1.0;
+// Counted from the start of the roll, a bit longer than the roll itself to recover
+const ROLL_FIRE_BLOCK_TIME: f32 = 0.4;
+
 // Plugin
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(spawn_player)
-            .add_system(player_movement)
-            .add_system(camera_lock.after(player_movement));
+        app.add_system_set(SystemSet::on_enter(AppState::Playing).with_system(spawn_player))
+            .add_system_set(
+                SystemSet::on_update(AppState::Playing)
+                    .with_system(dodge_roll.after(update_aim).before(player_movement))
+                    .with_system(player_movement)
+                    .with_system(camera_lock.after(player_movement)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::GameOver).with_system(despawn_all::<Player>),
+            );
     }
 }
 
 // Systems
-fn spawn_player(mut commands: Commands) {
+pub fn spawn_player(mut commands: Commands) {
     commands
         .spawn_bundle(SpriteBundle {
             sprite: Sprite {
@@ -39,45 +66,215 @@ fn spawn_player(mut commands: Commands) {
         .insert(Player)
         .insert(Name::new("Player"))
         .insert(Health(PLAYER_HEALTH))
+        .insert(MaxHealth(PLAYER_HEALTH))
         .insert(Invincible(false))
         .insert(InvincibilityTimer(Timer::from_seconds(
             PLAYER_INVINCIBILITY_TIME,
             false,
         )))
-        .insert(Speed(PLAYER_SPEED));
+        .insert(Speed(PLAYER_SPEED))
+        .insert(Velocity(Vec2::ZERO))
+        .insert(Acceleration(PLAYER_ACCELERATION))
+        .insert(Friction(PLAYER_FRICTION))
+        .insert(RollCooldown(finished_timer(ROLL_COOLDOWN)))
+        .insert(HitBox(Vec2::new(30.0, 30.0)))
+        .insert(BaseColor(PLAYER_COLOR))
+        .insert(StatusEffects::default());
 }
 
-// TODO: Consider moving all input handling to separate plugin
+/// Speeds the player up towards where they are heading and lets friction slow them down once they
+/// let go, capped at their `Speed`.
 pub fn player_movement(
-    keys: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
+    gamepad_input: GamepadInput,
     time: Res<Time>,
-    mut player_query: Query<(&mut Transform, &Speed), With<Player>>,
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &Speed,
+            &Acceleration,
+            &Friction,
+            Option<&SpeedBoost>,
+            &StatusEffects,
+            Option<&Roll>,
+        ),
+        With<Player>,
+    >,
 ) {
-    let (mut player_transform, player_speed) = player_query.single_mut();
-    let mut target_point: Vec2 = player_transform.translation.truncate();
+    let (
+        mut player_transform,
+        mut player_velocity,
+        player_speed,
+        player_acceleration,
+        player_friction,
+        speed_boost,
+        status_effects,
+        roll,
+    ) = player_query.single_mut();
 
-    if keys.pressed(KeyCode::W) {
-        target_point.y += 1.0;
-    }
-    if keys.pressed(KeyCode::A) {
-        target_point.x -= 1.0;
+    // Rolls move the player on their own
+    if roll.is_some() {
+        return;
     }
-    if keys.pressed(KeyCode::S) {
-        target_point.y -= 1.0;
-    }
-    if keys.pressed(KeyCode::D) {
-        target_point.x += 1.0;
+
+    if status_effects.is_frozen() {
+        **player_velocity = Vec2::ZERO;
+        return;
     }
 
-    let player_movement_vector = player_transform
-        .translation
-        .truncate()
-        .scaled_vector_to(&target_point, **player_speed * time.delta_seconds());
+    let speed = **player_speed
+        * speed_boost.map_or(1.0, |speed_boost| speed_boost.multiplier)
+        * status_effects.speed_multiplier();
+    let direction = movement_direction(&actions, &gamepad_input);
+
+    // Accelerating also brakes whatever is beyond the new top speed, e.g. when a boost runs out
+    let rate = if direction == Vec2::ZERO {
+        **player_friction
+    } else {
+        **player_acceleration
+    };
+    **player_velocity =
+        player_velocity.moved_towards(&(direction * speed), rate * time.delta_seconds());
+
+    let player_movement_vector = **player_velocity * time.delta_seconds();
 
     player_transform.translation.x += player_movement_vector.x;
     player_transform.translation.y += player_movement_vector.y;
 }
 
+/// Starts a roll on key press and carries the player through it. Rolls cover a fixed distance
+/// regardless of speed boosts or slows.
+fn dodge_roll(
+    mut commands: Commands,
+    actions: Res<Input<Action>>,
+    gamepad_input: GamepadInput,
+    time: Res<Time>,
+    aim_direction: Res<AimDirection>,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut RollCooldown,
+            Option<&mut Roll>,
+            &mut Invincible,
+            &mut InvincibilityTimer,
+        ),
+        With<Player>,
+    >,
+) {
+    let (
+        player_ent,
+        mut player_transform,
+        mut roll_cooldown,
+        roll,
+        mut invincible,
+        mut invincibility_timer,
+    ) = player_query.single_mut();
+
+    roll_cooldown.tick(time.delta());
+
+    if let Some(mut roll) = roll {
+        // Move by the share of the roll that passed this frame, so the distance comes out exact
+        let progress = roll.timer.percent();
+        roll.timer.tick(time.delta());
+
+        let roll_movement_vector =
+            roll.direction * ROLL_DISTANCE * (roll.timer.percent() - progress);
+        player_transform.translation.x += roll_movement_vector.x;
+        player_transform.translation.y += roll_movement_vector.y;
+
+        if roll.timer.finished() {
+            commands.entity(player_ent).remove::<Roll>();
+        }
+
+        return;
+    }
+
+    if !actions.just_pressed(Action::Roll) || !roll_cooldown.finished() {
+        return;
+    }
+
+    // Roll where the player is heading, or where they aim when standing still
+    let mut direction = movement_direction(&actions, &gamepad_input).normalize_or_zero();
+
+    if direction == Vec2::ZERO {
+        direction = **aim_direction;
+    }
+
+    if direction == Vec2::ZERO {
+        return;
+    }
+
+    commands.entity(player_ent).insert(Roll {
+        direction,
+        timer: Timer::from_seconds(ROLL_TIME, false),
+    });
+    roll_cooldown.reset();
+
+    // Shorten the invincibility window to the roll's, unless a hit already left more of it
+    let roll_invincibility = Duration::from_secs_f32(ROLL_INVINCIBILITY_TIME);
+    let remaining = invincibility_timer.duration() - invincibility_timer.elapsed();
+
+    if !**invincible || remaining < roll_invincibility {
+        let elapsed = invincibility_timer.duration() - roll_invincibility;
+
+        **invincible = true;
+        invincibility_timer.reset();
+        invincibility_timer.set_elapsed(elapsed);
+    }
+}
+
+/// Whether the player is still recovering from a roll and cannot shoot.
+pub fn firing_blocked(roll_cooldown: &RollCooldown) -> bool {
+    !roll_cooldown.finished() && roll_cooldown.elapsed_secs() < ROLL_FIRE_BLOCK_TIME
+}
+
+/// Movement actions when any of them is held, the left stick otherwise. Stick input keeps its
+/// magnitude for walking slower than full speed.
+fn movement_direction(actions: &Input<Action>, gamepad_input: &GamepadInput) -> Vec2 {
+    let mut direction = Vec2::ZERO;
+
+    if actions.pressed(Action::MoveUp) {
+        direction.y += 1.0;
+    }
+    if actions.pressed(Action::MoveLeft) {
+        direction.x -= 1.0;
+    }
+    if actions.pressed(Action::MoveDown) {
+        direction.y -= 1.0;
+    }
+    if actions.pressed(Action::MoveRight) {
+        direction.x += 1.0;
+    }
+
+    if direction == Vec2::ZERO {
+        return gamepad_input.movement();
+    }
+
+    // Diagonals are normalised, so they are no faster than straight lines
+    Vec2::ZERO.scaled_vector_to(&direction, 1.0)
+}
+
+/// Applies `damage` to the player and starts a new invincibility window, unless the player is
+/// still invincible from the previous hit. Returns whether the hit landed.
+pub fn hurt_player(
+    health: &mut Health,
+    invincible: &mut Invincible,
+    invincibility_timer: &mut InvincibilityTimer,
+    damage: i32,
+) -> bool {
+    if **invincible {
+        return false;
+    }
+
+    **health -= damage;
+    **invincible = true;
+    invincibility_timer.reset();
+
+    true
+}
+
 fn camera_lock(
     player_transform: Query<&Transform, With<Player>>,
     mut camera_transform: Query<&mut Transform, (With<MainCamera>, Without<Player>)>,
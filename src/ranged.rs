@@ -0,0 +1,253 @@
+use bevy::{prelude::*, sprite::collide_aabb::collide};
+
+use crate::{
+    ai::AiState,
+    app_state::{despawn_all, AppState},
+    components::{
+        AttackTimer, BaseColor, Damage, Enemy, EnemyBehaviour, EnemyProjectile, Health, HitBox,
+        InvincibilityTimer, Invincible, LifetimeTimer, Obstacle, Player, Velocity, WindUpTimer,
+        WindingUp,
+    },
+    enemies::enemy_movement,
+    player::hurt_player,
+    status_effects::{OnHitEffect, StatusEffect, StatusEffects},
+    util::VectorMath,
+};
+
+// Constants
+const PROJECTILE_COLOR: Color = Color::PINK;
+const PROJECTILE_SIZE: f32 = 10.0;
+const PROJECTILE_LIFETIME: f32 = 4.0;
+
+// Plugin
+pub struct RangedPlugin;
+
+impl Plugin for RangedPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(ranged_attack.after(enemy_movement))
+                .with_system(projectile_movement)
+                .with_system(projectile_hits.after(projectile_movement)),
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::GameOver).with_system(despawn_all::<EnemyProjectile>),
+        );
+    }
+}
+
+// Systems
+fn ranged_attack(
+    mut commands: Commands,
+    time: Res<Time>,
+    player_transform: Query<&Transform, With<Player>>,
+    mut enemy_query: Query<
+        (
+            &Transform,
+            &EnemyBehaviour,
+            &BaseColor,
+            &mut Sprite,
+            &mut AttackTimer,
+            &mut WindUpTimer,
+            &mut WindingUp,
+            &OnHitEffect,
+            &AiState,
+            &StatusEffects,
+        ),
+        (With<Enemy>, Without<Player>),
+    >,
+) {
+    let player_position = player_transform.single().translation.truncate();
+
+    for (
+        enemy_transform,
+        enemy_behaviour,
+        base_color,
+        mut sprite,
+        mut attack_timer,
+        mut wind_up_timer,
+        mut winding_up,
+        on_hit_effect,
+        ai_state,
+        status_effects,
+    ) in enemy_query.iter_mut()
+    {
+        // Frozen enemies hold their shot, wind-up included, until they thaw
+        if status_effects.is_frozen() {
+            continue;
+        }
+
+        let (projectile_speed, projectile_damage) = match enemy_behaviour {
+            EnemyBehaviour::Ranged {
+                projectile_speed,
+                projectile_damage,
+                ..
+            } => (projectile_speed, projectile_damage),
+            _ => continue,
+        };
+        let enemy_position = enemy_transform.translation.truncate();
+
+        if **winding_up {
+            wind_up_timer.tick(time.delta());
+
+            // Telegraph the shot by flashing towards white
+            let [red, green, blue, alpha] = base_color.as_rgba_f32();
+            let flash = wind_up_timer.percent();
+            sprite.color = Color::rgba(
+                red + (1.0 - red) * flash,
+                green + (1.0 - green) * flash,
+                blue + (1.0 - blue) * flash,
+                alpha,
+            );
+
+            if wind_up_timer.just_finished() {
+                **winding_up = false;
+                sprite.color = **base_color;
+                attack_timer.reset();
+
+                // Aim at where the player is right now, so moving sideways dodges the shot
+                let velocity = enemy_position.scaled_vector_to(&player_position, *projectile_speed);
+
+                spawn_projectile(
+                    &mut commands,
+                    enemy_transform.translation,
+                    velocity,
+                    *projectile_damage,
+                    **on_hit_effect,
+                );
+            }
+        } else {
+            attack_timer.tick(time.delta());
+
+            // Only wind up once the state machine decided the player is in range
+            if attack_timer.finished() && *ai_state == AiState::Attack {
+                **winding_up = true;
+                wind_up_timer.reset();
+            }
+        }
+    }
+}
+
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    translation: Vec3,
+    velocity: Vec2,
+    damage: i32,
+    on_hit_effect: Option<StatusEffect>,
+) {
+    let size = Vec2::splat(PROJECTILE_SIZE);
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: PROJECTILE_COLOR,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_translation(translation),
+            ..default()
+        })
+        .insert(EnemyProjectile)
+        .insert(Name::new("EnemyProjectile"))
+        .insert(Velocity(velocity))
+        .insert(Damage(damage))
+        .insert(OnHitEffect(on_hit_effect))
+        .insert(HitBox(size))
+        .insert(LifetimeTimer(Timer::from_seconds(
+            PROJECTILE_LIFETIME,
+            false,
+        )));
+}
+
+fn projectile_movement(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut projectile_query: Query<
+        (Entity, &mut Transform, &Velocity, &mut LifetimeTimer),
+        With<EnemyProjectile>,
+    >,
+) {
+    for (projectile_ent, mut projectile_transform, velocity, mut lifetime_timer) in
+        projectile_query.iter_mut()
+    {
+        projectile_transform.translation += velocity.extend(0.0) * time.delta_seconds();
+
+        lifetime_timer.tick(time.delta());
+
+        if lifetime_timer.finished() {
+            commands.entity(projectile_ent).despawn();
+        }
+    }
+}
+
+fn projectile_hits(
+    mut commands: Commands,
+    mut player_query: Query<
+        (
+            &Transform,
+            &HitBox,
+            &mut Health,
+            &mut Invincible,
+            &mut InvincibilityTimer,
+            &mut StatusEffects,
+        ),
+        With<Player>,
+    >,
+    projectile_query: Query<
+        (Entity, &Transform, &HitBox, &Damage, &OnHitEffect),
+        With<EnemyProjectile>,
+    >,
+    obstacle_query: Query<(&Transform, &HitBox), With<Obstacle>>,
+) {
+    let (
+        player_transform,
+        player_hit_box,
+        mut player_health,
+        mut invincible,
+        mut invincibility_timer,
+        mut player_status_effects,
+    ) = player_query.single_mut();
+
+    for (
+        projectile_ent,
+        projectile_transform,
+        projectile_hit_box,
+        projectile_damage,
+        on_hit_effect,
+    ) in projectile_query.iter()
+    {
+        let hits = |transform: &Transform, hit_box: &HitBox| {
+            collide(
+                projectile_transform.translation,
+                **projectile_hit_box,
+                transform.translation,
+                **hit_box,
+            )
+            .is_some()
+        };
+
+        // Invincible players, e.g. mid-roll, let projectiles fly through them
+        let hurt = hits(player_transform, player_hit_box)
+            && hurt_player(
+                &mut player_health,
+                &mut invincible,
+                &mut invincibility_timer,
+                **projectile_damage,
+            );
+
+        if hurt {
+            if let Some(effect) = **on_hit_effect {
+                player_status_effects.apply(effect);
+            }
+
+            commands.entity(projectile_ent).despawn();
+        } else if obstacle_query
+            .iter()
+            .any(|(obstacle_transform, obstacle_hit_box)| {
+                hits(obstacle_transform, obstacle_hit_box)
+            })
+        {
+            commands.entity(projectile_ent).despawn();
+        }
+    }
+}
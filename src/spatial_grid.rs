@@ -0,0 +1,178 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    app_state::AppState,
+    components::{Enemy, HitBox},
+    util::VectorMath,
+};
+
+// Constants
+const CELL_SIZE: f32 = 64.0;
+
+// Resources
+pub struct GridEntry {
+    pub entity: Entity,
+    pub position: Vec2,
+    /// Half the largest side of the entity's hit box
+    pub radius: f32,
+}
+
+/// Buckets enemies by position once per frame, so hit tests and neighbour searches only have to
+/// look at the cells around them instead of every enemy.
+#[derive(Default)]
+pub struct SpatialGrid {
+    cells: HashMap<IVec2, Vec<GridEntry>>,
+    /// Entries are bucketed by their centre, so queries look this much further to catch
+    /// everything that overlaps them
+    max_radius: f32,
+}
+
+impl SpatialGrid {
+    pub fn clear(&mut self) {
+        // Keep the buckets around, the same cells are likely to be filled again next frame
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+
+        self.max_radius = 0.0;
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2, radius: f32) {
+        self.cells
+            .entry(Self::cell(position))
+            .or_default()
+            .push(GridEntry {
+                entity,
+                position,
+                radius,
+            });
+
+        self.max_radius = self.max_radius.max(radius);
+    }
+
+    /// Entries whose circle overlaps the circle around `center`.
+    pub fn query_radius(&self, center: Vec2, radius: f32) -> impl Iterator<Item = &GridEntry> {
+        self.entries_near(center - radius, center + radius)
+            .filter(move |entry| entry.position.distance(center) <= radius + entry.radius)
+    }
+
+    /// Entries whose hit box overlaps the box of the given size around `center`.
+    pub fn query_aabb(&self, center: Vec2, size: Vec2) -> impl Iterator<Item = &GridEntry> {
+        let half_size = size / 2.0;
+
+        self.entries_near(center - half_size, center + half_size)
+            .filter(move |entry| {
+                ((entry.position - center).abs() - entry.radius)
+                    .cmple(half_size)
+                    .all()
+            })
+    }
+
+    /// Entries whose centre lies in the triangle, as used for cone-shaped weapon hits.
+    pub fn query_triangle(&self, a: Vec2, b: Vec2, c: Vec2) -> impl Iterator<Item = &GridEntry> {
+        self.entries_near(a.min(b).min(c), a.max(b).max(c))
+            .filter(move |entry| entry.position.is_in_triangle(&a, &b, &c))
+    }
+
+    fn entries_near(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = &GridEntry> {
+        let min_cell = Self::cell(min - self.max_radius);
+        let max_cell = Self::cell(max + self.max_radius);
+
+        (min_cell.x..=max_cell.x)
+            .flat_map(move |x| (min_cell.y..=max_cell.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+    }
+
+    fn cell(position: Vec2) -> IVec2 {
+        (position / CELL_SIZE).floor().as_ivec2()
+    }
+}
+
+// Plugin
+pub struct SpatialGridPlugin;
+
+impl Plugin for SpatialGridPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing).with_system(update_spatial_grid),
+        )
+        .init_resource::<SpatialGrid>();
+    }
+}
+
+// Systems
+pub fn update_spatial_grid(
+    mut spatial_grid: ResMut<SpatialGrid>,
+    enemy_query: Query<(Entity, &Transform, &HitBox), With<Enemy>>,
+) {
+    spatial_grid.clear();
+
+    for (enemy_ent, enemy_transform, enemy_hit_box) in enemy_query.iter() {
+        spatial_grid.insert(
+            enemy_ent,
+            enemy_transform.translation.truncate(),
+            enemy_hit_box.max_element() / 2.0,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spatial_grid() -> SpatialGrid {
+        let mut spatial_grid = SpatialGrid::default();
+
+        spatial_grid.insert(Entity::from_raw(0), Vec2::new(0.0, 0.0), 10.0);
+        spatial_grid.insert(Entity::from_raw(1), Vec2::new(100.0, 0.0), 10.0);
+        spatial_grid.insert(Entity::from_raw(2), Vec2::new(-300.0, 250.0), 40.0);
+
+        spatial_grid
+    }
+
+    fn found<'a>(entries: impl Iterator<Item = &'a GridEntry>) -> Vec<u32> {
+        let mut found: Vec<u32> = entries.map(|entry| entry.entity.id()).collect();
+        found.sort_unstable();
+
+        found
+    }
+
+    #[test]
+    fn radius_query_includes_overlapping_entries() {
+        let spatial_grid = spatial_grid();
+
+        assert_eq!(found(spatial_grid.query_radius(Vec2::ZERO, 50.0)), [0]);
+        assert_eq!(found(spatial_grid.query_radius(Vec2::ZERO, 90.0)), [0, 1]);
+        assert_eq!(
+            found(spatial_grid.query_radius(Vec2::new(-250.0, 250.0), 15.0)),
+            [2]
+        );
+    }
+
+    #[test]
+    fn aabb_and_triangle_queries() {
+        let spatial_grid = spatial_grid();
+
+        assert_eq!(
+            found(spatial_grid.query_aabb(Vec2::new(50.0, 0.0), Vec2::new(90.0, 10.0))),
+            [0, 1]
+        );
+        assert_eq!(
+            found(spatial_grid.query_triangle(
+                Vec2::new(50.0, 0.0),
+                Vec2::new(200.0, 100.0),
+                Vec2::new(200.0, -100.0),
+            )),
+            [1]
+        );
+    }
+
+    #[test]
+    fn cleared_grid_is_empty() {
+        let mut spatial_grid = spatial_grid();
+        spatial_grid.clear();
+
+        assert_eq!(spatial_grid.query_radius(Vec2::ZERO, 1000.0).count(), 0);
+    }
+}
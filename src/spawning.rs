@@ -0,0 +1,274 @@
+use std::f32::consts::PI;
+
+use bevy::{ecs::system::SystemParam, prelude::*, sprite::collide_aabb::collide};
+use rand::{prelude::SliceRandom, random};
+
+use crate::{
+    ai::AiState,
+    app_state::{despawn_all, AppState},
+    arena::ARENA_HALF_EXTENTS,
+    components::{Enemy, HitBox, LeashPolicy, MainCamera, Obstacle, Player, SpawnPoint},
+    enemies::spawn_enemy,
+    enemy_kinds::EnemyKind,
+    util::VectorMath,
+};
+
+// Constants
+const SPAWN_DISTANCE_MIN: f32 = 500.0;
+const SPAWN_DISTANCE_MAX: f32 = 900.0;
+const SPAWN_POINT_RANGE: f32 = 1200.0;
+const SPAWN_ATTEMPTS: usize = 16;
+// Extra room kept to the screen edge so enemies do not pop in right at the border
+const OFF_SCREEN_MARGIN: f32 = 50.0;
+
+const SPAWN_MARKER_TIME: f32 = 1.0;
+const SPAWN_MARKER_ALPHA: f32 = 0.4;
+const SPAWN_MARKER_PULSE_RATE: f32 = 12.0;
+
+// Enemies further away from the player than this are culled or recycled
+pub const LEASH_DISTANCE: f32 = 1400.0;
+// Half of the cone ahead of the player that recycled enemies are moved into
+const RECYCLE_SPREAD: f32 = PI / 3.0;
+
+// Spawn markers
+/// Warning shown where an enemy is about to appear.
+#[derive(Component)]
+pub struct SpawnMarker {
+    enemy_kind: EnemyKind,
+    timer: Timer,
+}
+
+// Resources
+/// Where the player stood when the leash was last checked, to tell which way they are heading.
+#[derive(Default, Deref, DerefMut)]
+struct LastPlayerPosition(Option<Vec2>);
+
+// System params
+/// Everything needed to decide where enemies may appear.
+#[derive(SystemParam)]
+pub struct SpawnPlacement<'w, 's> {
+    windows: Res<'w, Windows>,
+    camera_query: Query<'w, 's, &'static Transform, With<MainCamera>>,
+    obstacle_query: Query<'w, 's, (&'static Transform, &'static HitBox), With<Obstacle>>,
+    spawn_point_query: Query<'w, 's, &'static Transform, With<SpawnPoint>>,
+}
+
+impl<'w, 's> SpawnPlacement<'w, 's> {
+    /// Finds a spot around `center` for something of the given size, preferring designer-placed
+    /// spawn points, then random off-screen positions, and only then anything valid on screen.
+    pub fn find(&self, center: Vec2, size: Vec2) -> Option<Vec2> {
+        let spawn_points: Vec<Vec2> = self
+            .spawn_point_query
+            .iter()
+            .map(|spawn_point_transform| spawn_point_transform.translation.truncate())
+            .filter(|position| position.distance(center) <= SPAWN_POINT_RANGE)
+            .filter(|position| self.is_valid(*position, size) && !self.is_on_screen(*position))
+            .collect();
+
+        if let Some(position) = spawn_points.choose(&mut rand::thread_rng()) {
+            return Some(*position);
+        }
+
+        let mut on_screen_fallback = None;
+
+        for _ in 0..SPAWN_ATTEMPTS {
+            let angle = random::<f32>() * 2.0 * PI;
+            let distance =
+                SPAWN_DISTANCE_MIN + random::<f32>() * (SPAWN_DISTANCE_MAX - SPAWN_DISTANCE_MIN);
+            let position = center + Vec2::X.rotated_by(angle) * distance;
+
+            if !self.is_valid(position, size) {
+                continue;
+            }
+
+            if !self.is_on_screen(position) {
+                return Some(position);
+            }
+
+            on_screen_fallback.get_or_insert(position);
+        }
+
+        on_screen_fallback
+    }
+
+    /// Like `find`, but picks an off-screen spot in the cone around `heading` when possible, so
+    /// the player runs into whatever is placed there.
+    pub fn find_ahead(&self, center: Vec2, heading: Vec2, size: Vec2) -> Option<Vec2> {
+        if heading != Vec2::ZERO {
+            let heading_angle = heading.y.atan2(heading.x);
+
+            for _ in 0..SPAWN_ATTEMPTS {
+                let angle = heading_angle + (random::<f32>() * 2.0 - 1.0) * RECYCLE_SPREAD;
+                let distance = SPAWN_DISTANCE_MIN
+                    + random::<f32>() * (SPAWN_DISTANCE_MAX - SPAWN_DISTANCE_MIN);
+                let position = center + Vec2::X.rotated_by(angle) * distance;
+
+                if self.is_valid(position, size) && !self.is_on_screen(position) {
+                    return Some(position);
+                }
+            }
+        }
+
+        self.find(center, size)
+    }
+
+    /// Finds a free spot `distance` away from `center`, on screen or not, e.g. for adds appearing
+    /// right next to whoever summoned them.
+    pub fn find_around(&self, center: Vec2, distance: f32, size: Vec2) -> Option<Vec2> {
+        (0..SPAWN_ATTEMPTS)
+            .map(|_| center + Vec2::X.rotated_by(random::<f32>() * 2.0 * PI) * distance)
+            .find(|position| self.is_valid(*position, size))
+    }
+
+    fn is_valid(&self, position: Vec2, size: Vec2) -> bool {
+        let inside_arena = position.abs().cmple(ARENA_HALF_EXTENTS - size / 2.0).all();
+
+        inside_arena
+            && !self
+                .obstacle_query
+                .iter()
+                .any(|(obstacle_transform, obstacle_hit_box)| {
+                    collide(
+                        position.extend(0.0),
+                        size,
+                        obstacle_transform.translation,
+                        **obstacle_hit_box,
+                    )
+                    .is_some()
+                })
+    }
+
+    fn is_on_screen(&self, position: Vec2) -> bool {
+        let window = match self.windows.get_primary() {
+            Some(window) => window,
+            None => return false,
+        };
+        let camera_position = match self.camera_query.get_single() {
+            Ok(camera_transform) => camera_transform.translation.truncate(),
+            Err(_) => return false,
+        };
+        let half_view = Vec2::new(window.width(), window.height()) / 2.0 + OFF_SCREEN_MARGIN;
+
+        (position - camera_position).abs().cmplt(half_view).all()
+    }
+}
+
+// Plugin
+pub struct SpawningPlugin;
+
+impl Plugin for SpawningPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(materialise_enemies)
+                .with_system(leash_enemies),
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::GameOver)
+                .with_system(despawn_all::<SpawnMarker>)
+                .with_system(reset_last_player_position),
+        )
+        .init_resource::<LastPlayerPosition>();
+    }
+}
+
+/// Shows a warning marker at `position`, which turns into an enemy of the given kind shortly
+/// after.
+pub fn spawn_marker(commands: &mut Commands, enemy_kind: &EnemyKind, position: Vec2) {
+    let mut color = enemy_kind.color;
+    color.set_a(SPAWN_MARKER_ALPHA);
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::splat(enemy_kind.size)),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(0.0)),
+            ..default()
+        })
+        .insert(Name::new("SpawnMarker"))
+        .insert(SpawnMarker {
+            enemy_kind: enemy_kind.clone(),
+            timer: Timer::from_seconds(SPAWN_MARKER_TIME, false),
+        });
+}
+
+// Systems
+fn materialise_enemies(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut marker_query: Query<(Entity, &Transform, &mut Sprite, &mut SpawnMarker)>,
+) {
+    for (marker_ent, marker_transform, mut marker_sprite, mut spawn_marker) in
+        marker_query.iter_mut()
+    {
+        spawn_marker.timer.tick(time.delta());
+
+        // Blink faster the closer the enemy is to appearing
+        let progress = spawn_marker.timer.percent();
+        let pulse = (progress * progress * SPAWN_MARKER_PULSE_RATE * PI).cos() * 0.5 + 0.5;
+        marker_sprite.color.set_a(SPAWN_MARKER_ALPHA * pulse);
+
+        if spawn_marker.timer.finished() {
+            spawn_enemy(
+                &mut commands,
+                &spawn_marker.enemy_kind,
+                marker_transform.translation,
+                AiState::Chase,
+            );
+
+            commands.entity(marker_ent).despawn();
+        }
+    }
+}
+
+/// Despawns or recycles enemies that fell too far behind, depending on their kind's policy.
+fn leash_enemies(
+    mut commands: Commands,
+    mut last_player_position: ResMut<LastPlayerPosition>,
+    spawn_placement: SpawnPlacement,
+    player_transform: Query<&Transform, With<Player>>,
+    mut enemy_query: Query<
+        (Entity, &mut Transform, &HitBox, &LeashPolicy),
+        (
+            With<Enemy>,
+            Without<Player>,
+            Without<MainCamera>,
+            Without<Obstacle>,
+            Without<SpawnPoint>,
+        ),
+    >,
+) {
+    let player_position = player_transform.single().translation.truncate();
+    // Ahead is wherever the player moved since last frame
+    let heading = player_position - last_player_position.unwrap_or(player_position);
+    **last_player_position = Some(player_position);
+
+    for (enemy_ent, mut enemy_transform, enemy_hit_box, leash_policy) in enemy_query.iter_mut() {
+        if enemy_transform
+            .translation
+            .truncate()
+            .distance(player_position)
+            <= LEASH_DISTANCE
+        {
+            continue;
+        }
+
+        match leash_policy {
+            LeashPolicy::Despawn => commands.entity(enemy_ent).despawn_recursive(),
+            LeashPolicy::Teleport => {
+                if let Some(position) =
+                    spawn_placement.find_ahead(player_position, heading, **enemy_hit_box)
+                {
+                    enemy_transform.translation = position.extend(enemy_transform.translation.z);
+                }
+            }
+        }
+    }
+}
+
+fn reset_last_player_position(mut last_player_position: ResMut<LastPlayerPosition>) {
+    **last_player_position = None;
+}
@@ -0,0 +1,275 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    app_state::AppState,
+    components::{BaseColor, Health, WindingUp},
+    enemies::enemy_deaths,
+};
+
+// Constants
+// How far an afflicted sprite is blended from its base colour towards the effect's tint
+const TINT_STRENGTH: f32 = 0.6;
+
+// Status effects
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum StatusEffect {
+    Burning,
+    Slow,
+    Poison,
+    Freeze,
+}
+
+enum Stacking {
+    /// Reapplying only restarts the duration
+    Refresh,
+    /// Reapplying restarts the duration and adds a stack, multiplying the damage per tick
+    Intensity { max_stacks: u32 },
+}
+
+struct StatusEffectStats {
+    duration: f32,
+    tick_interval: f32,
+    /// Damage dealt per stack every `tick_interval` seconds
+    tick_damage: i32,
+    speed_multiplier: f32,
+    tint: Color,
+    stacking: Stacking,
+}
+
+impl StatusEffect {
+    fn stats(&self) -> StatusEffectStats {
+        match self {
+            StatusEffect::Burning => StatusEffectStats {
+                duration: 3.0,
+                tick_interval: 0.5,
+                tick_damage: 3,
+                speed_multiplier: 1.0,
+                tint: Color::ORANGE_RED,
+                stacking: Stacking::Refresh,
+            },
+            StatusEffect::Slow => StatusEffectStats {
+                duration: 2.0,
+                tick_interval: 1.0,
+                tick_damage: 0,
+                speed_multiplier: 0.5,
+                tint: Color::ALICE_BLUE,
+                stacking: Stacking::Refresh,
+            },
+            StatusEffect::Poison => StatusEffectStats {
+                duration: 5.0,
+                tick_interval: 1.0,
+                tick_damage: 1,
+                speed_multiplier: 1.0,
+                tint: Color::LIME_GREEN,
+                stacking: Stacking::Intensity { max_stacks: 5 },
+            },
+            StatusEffect::Freeze => StatusEffectStats {
+                duration: 1.0,
+                tick_interval: 1.0,
+                tick_damage: 0,
+                speed_multiplier: 0.0,
+                tint: Color::CYAN,
+                stacking: Stacking::Refresh,
+            },
+        }
+    }
+}
+
+pub struct ActiveEffect {
+    effect: StatusEffect,
+    stacks: u32,
+    timer: Timer,
+    tick_timer: Timer,
+}
+
+/// Timed effects currently afflicting an enemy or the player, least recently applied first.
+#[derive(Component, Default, Deref, DerefMut)]
+pub struct StatusEffects(Vec<ActiveEffect>);
+
+impl StatusEffects {
+    pub fn apply(&mut self, effect: StatusEffect) {
+        let stats = effect.stats();
+
+        // Reapplied effects move to the back, so the tint follows whatever hit last
+        if let Some(index) = self.iter().position(|active| active.effect == effect) {
+            let mut active = self.remove(index);
+            active.timer.reset();
+
+            if let Stacking::Intensity { max_stacks } = stats.stacking {
+                active.stacks = (active.stacks + 1).min(max_stacks);
+            }
+
+            self.push(active);
+            return;
+        }
+
+        self.push(ActiveEffect {
+            effect,
+            stacks: 1,
+            timer: Timer::from_seconds(stats.duration, false),
+            tick_timer: Timer::from_seconds(stats.tick_interval, true),
+        });
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        self.iter()
+            .map(|active| active.effect.stats().speed_multiplier)
+            .product()
+    }
+
+    /// Frozen enemies and players stand still until the freeze wears off, and enemies hold their
+    /// attacks meanwhile.
+    pub fn is_frozen(&self) -> bool {
+        self.iter()
+            .any(|active| active.effect == StatusEffect::Freeze)
+    }
+
+    /// Tint of the most recently applied effect that is still running.
+    fn tint(&self) -> Option<Color> {
+        self.last().map(|active| active.effect.stats().tint)
+    }
+
+    /// Advances every effect by `delta`, drops the ones that ran out and returns the damage dealt
+    /// in the meantime.
+    fn tick(&mut self, delta: Duration) -> i32 {
+        let mut damage = 0;
+
+        for active in self.iter_mut() {
+            active.timer.tick(delta);
+            active.tick_timer.tick(delta);
+
+            damage += active.effect.stats().tick_damage
+                * active.stacks as i32
+                * active.tick_timer.times_finished() as i32;
+        }
+
+        self.retain(|active| !active.timer.finished());
+
+        damage
+    }
+}
+
+/// Effect inflicted on whatever this weapon, enemy or projectile hits.
+#[derive(Component, Deref, DerefMut)]
+pub struct OnHitEffect(pub Option<StatusEffect>);
+
+// Plugin
+pub struct StatusEffectPlugin;
+
+impl Plugin for StatusEffectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(tick_status_effects.before(enemy_deaths))
+                .with_system(tint_status_effects.after(tick_status_effects)),
+        );
+    }
+}
+
+// Systems
+/// Deals damage over time, ignoring the player's invincibility.
+fn tick_status_effects(
+    time: Res<Time>,
+    mut afflicted_query: Query<(&mut Health, &mut StatusEffects)>,
+) {
+    for (mut health, mut status_effects) in afflicted_query.iter_mut() {
+        if status_effects.is_empty() {
+            continue;
+        }
+
+        let damage = status_effects.tick(time.delta());
+
+        if damage != 0 {
+            **health -= damage;
+        }
+    }
+}
+
+fn tint_status_effects(
+    mut afflicted_query: Query<(&StatusEffects, &BaseColor, &mut Sprite, Option<&WindingUp>)>,
+) {
+    for (status_effects, base_color, mut sprite, winding_up) in afflicted_query.iter_mut() {
+        // The attack telegraph takes over the sprite colour while it lasts
+        if matches!(winding_up, Some(WindingUp(true))) {
+            continue;
+        }
+
+        let color = match status_effects.tint() {
+            Some(tint) => {
+                let [red, green, blue, alpha] = base_color.as_rgba_f32();
+                let [tint_red, tint_green, tint_blue, _] = tint.as_rgba_f32();
+
+                Color::rgba(
+                    red + (tint_red - red) * TINT_STRENGTH,
+                    green + (tint_green - green) * TINT_STRENGTH,
+                    blue + (tint_blue - blue) * TINT_STRENGTH,
+                    alpha,
+                )
+            }
+            None => **base_color,
+        };
+
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacking_rules() {
+        let mut status_effects = StatusEffects::default();
+
+        for _ in 0..10 {
+            status_effects.apply(StatusEffect::Burning);
+            status_effects.apply(StatusEffect::Poison);
+        }
+
+        assert_eq!(status_effects.len(), 2);
+        assert_eq!(status_effects[0].stacks, 1);
+        assert_eq!(status_effects[1].stacks, 5);
+    }
+
+    #[test]
+    fn tint_follows_latest_application() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.apply(StatusEffect::Burning);
+        status_effects.apply(StatusEffect::Slow);
+        assert_eq!(status_effects.tint(), Some(Color::ALICE_BLUE));
+
+        status_effects.apply(StatusEffect::Burning);
+        assert_eq!(status_effects.tint(), Some(Color::ORANGE_RED));
+    }
+
+    #[test]
+    fn damage_over_time_expires() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.apply(StatusEffect::Poison);
+        status_effects.apply(StatusEffect::Poison);
+
+        assert_eq!(status_effects.tick(Duration::from_secs_f32(1.0)), 2);
+        assert_eq!(status_effects.tick(Duration::from_secs_f32(4.0)), 8);
+        assert!(status_effects.is_empty());
+    }
+
+    #[test]
+    fn speed_multipliers_combine() {
+        let mut status_effects = StatusEffects::default();
+        assert_eq!(status_effects.speed_multiplier(), 1.0);
+
+        status_effects.apply(StatusEffect::Slow);
+        status_effects.apply(StatusEffect::Burning);
+        assert_eq!(status_effects.speed_multiplier(), 0.5);
+        assert!(!status_effects.is_frozen());
+
+        status_effects.apply(StatusEffect::Freeze);
+        assert_eq!(status_effects.speed_multiplier(), 0.0);
+        assert!(status_effects.is_frozen());
+    }
+}
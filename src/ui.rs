@@ -1,33 +1,49 @@
 use bevy::prelude::*;
 
 use crate::{
+    app_state::{despawn_all, AppState},
     components::{
-        AmmoText, CurrentAmmo, Enemy, EnemyText, Health, HealthText, Player, Weapon, WeaponText,
+        AmmoText, Boss, BossHealthBar, BossHealthBarFill, CurrencyText, CurrentAmmo, Enemy,
+        EnemyText, Health, HealthText, Hud, MaxHealth, Player, WaveText, Weapon, WeaponText,
     },
     enemies::enemy_movement,
+    loot::Currency,
+    waves::{WaveCleared, WaveStarted},
     weapons::SelectedWeapon,
 };
 
 // Constants
 const TEXT_COLOR: Color = Color::WHITE;
+const BOSS_HEALTH_BAR_COLOR: Color = Color::CRIMSON;
+const BOSS_HEALTH_BAR_BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
 
 // Plugin
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_ui)
-            .add_system(update_health.after(enemy_movement))
-            .add_system(update_selected_weapon)
-            .add_system(update_current_ammo)
-            .add_system(update_enemy_count);
+        app.add_startup_system(spawn_ui_camera)
+            .add_system_set(SystemSet::on_enter(AppState::Playing).with_system(setup_ui))
+            .add_system_set(
+                SystemSet::on_update(AppState::Playing)
+                    .with_system(update_health.after(enemy_movement))
+                    .with_system(update_selected_weapon)
+                    .with_system(update_current_ammo)
+                    .with_system(update_enemy_count)
+                    .with_system(update_wave)
+                    .with_system(update_currency)
+                    .with_system(update_boss_health_bar),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(despawn_all::<Hud>));
     }
 }
 
 // Systems
-fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn spawn_ui_camera(mut commands: Commands) {
     commands.spawn_bundle(UiCameraBundle::default());
+}
 
+fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
     let font = asset_server.load("fonts/FiraSans-Bold.ttf");
 
     commands
@@ -47,6 +63,7 @@ fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
             color: Color::rgba(0.0, 0.0, 0.0, 0.0).into(),
             ..default()
         })
+        .insert(Hud)
         .with_children(|parent| {
             parent
                 .spawn_bundle(TextBundle {
@@ -139,6 +156,85 @@ fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
                     ..default()
                 })
                 .insert(EnemyText);
+
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect {
+                            left: Val::Px(5.0),
+                            right: Val::Px(5.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    text: Text::with_section(
+                        "Wave: %",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 30.0,
+                            color: TEXT_COLOR,
+                        },
+                        Default::default(),
+                    ),
+                    ..default()
+                })
+                .insert(WaveText);
+
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect {
+                            left: Val::Px(5.0),
+                            right: Val::Px(5.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    text: Text::with_section(
+                        "Coins: %",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 30.0,
+                            color: TEXT_COLOR,
+                        },
+                        Default::default(),
+                    ),
+                    ..default()
+                })
+                .insert(CurrencyText);
+        });
+
+    // Boss health bar, hidden until a boss shows up
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(60.0), Val::Px(20.0)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Percent(20.0),
+                    top: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            color: BOSS_HEALTH_BAR_BACKGROUND_COLOR.into(),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(BossHealthBar)
+        .insert(Hud)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                        ..default()
+                    },
+                    color: BOSS_HEALTH_BAR_COLOR.into(),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(BossHealthBarFill);
         });
 }
 
@@ -191,3 +287,49 @@ fn update_enemy_count(
 
     enemy_count_text.sections[0].value = format!("Enemies: {}", enemy_count);
 }
+
+fn update_wave(
+    mut wave_started: EventReader<WaveStarted>,
+    mut wave_cleared: EventReader<WaveCleared>,
+    mut wave_text: Query<&mut Text, With<WaveText>>,
+) {
+    let mut wave_text = wave_text.single_mut();
+
+    for WaveStarted(wave) in wave_started.iter() {
+        wave_text.sections[0].value = format!("Wave: {}", wave);
+    }
+    for WaveCleared(wave) in wave_cleared.iter() {
+        wave_text.sections[0].value = format!("Wave: {} cleared", wave);
+    }
+}
+
+fn update_currency(
+    currency: Res<Currency>,
+    mut currency_text: Query<&mut Text, With<CurrencyText>>,
+) {
+    let mut currency_text = currency_text.single_mut();
+
+    currency_text.sections[0].value = format!("Coins: {}", **currency);
+}
+
+fn update_boss_health_bar(
+    boss_health: Query<(&Health, &MaxHealth), With<Boss>>,
+    mut boss_health_bar_visibility: Query<
+        &mut Visibility,
+        Or<(With<BossHealthBar>, With<BossHealthBarFill>)>,
+    >,
+    mut boss_health_bar_fill: Query<&mut Style, With<BossHealthBarFill>>,
+) {
+    let boss_health = boss_health.iter().next();
+
+    // Visibility is not inherited by UI children, so the fill has to be toggled as well
+    for mut visibility in boss_health_bar_visibility.iter_mut() {
+        visibility.is_visible = boss_health.is_some();
+    }
+
+    if let Some((health, max_health)) = boss_health {
+        let fill = (**health).max(0) as f32 / **max_health as f32;
+
+        boss_health_bar_fill.single_mut().size.width = Val::Percent(100.0 * fill);
+    }
+}
@@ -4,6 +4,16 @@ pub(crate) trait VectorMath {
     fn scaled_vector_to(&self, to: &Self, scale: f32) -> Self;
     fn is_in_triangle(&self, a: &Self, b: &Self, c: &Self) -> bool;
     fn rotated_by(&self, angle: f32) -> Self;
+    fn steered_towards(&self, target: &Self, max_turn: f32, max_speed_change: f32) -> Self;
+    fn moved_towards(&self, target: &Self, max_distance: f32) -> Self;
+}
+
+/// A one-shot timer of `seconds` that has already run out, for cooldowns that start out ready.
+pub(crate) fn finished_timer(seconds: f32) -> Timer {
+    let mut timer = Timer::from_seconds(seconds, false);
+    timer.tick(timer.duration());
+
+    timer
 }
 
 impl VectorMath for Vec2 {
@@ -28,6 +38,35 @@ impl VectorMath for Vec2 {
 
         Vec2::new(rotated_x, rotated_y)
     }
+
+    fn steered_towards(&self, target: &Self, max_turn: f32, max_speed_change: f32) -> Self {
+        let speed = self.length();
+        let target_speed = target.length();
+        let new_speed = speed + (target_speed - speed).clamp(-max_speed_change, max_speed_change);
+
+        // Without a current heading there is nothing to turn, and without a target heading
+        // there is nothing to turn towards
+        if speed == 0.0 {
+            return target.normalize_or_zero() * new_speed;
+        }
+        if target_speed == 0.0 {
+            return *self / speed * new_speed;
+        }
+
+        let angle = self.angle_between(*target).clamp(-max_turn, max_turn);
+
+        (*self / speed).rotated_by(angle) * new_speed
+    }
+
+    fn moved_towards(&self, target: &Self, max_distance: f32) -> Self {
+        let delta = *target - *self;
+
+        if delta.length() <= max_distance {
+            return *target;
+        }
+
+        *self + delta.normalize() * max_distance
+    }
 }
 
 #[cfg(test)]
@@ -63,4 +102,34 @@ mod tests {
 
         assert!(point.is_in_triangle(&a, &b, &c));
     }
+
+    #[test]
+    fn steering_is_limited() {
+        let velocity = Vec2::new(10.0, 0.0);
+        let steered = velocity.steered_towards(&Vec2::new(0.0, 20.0), 0.5, 5.0);
+
+        assert!((steered.length() - 15.0).abs() < 1e-4);
+        assert!((velocity.angle_between(steered) - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn steering_from_standstill() {
+        let steered = Vec2::ZERO.steered_towards(&Vec2::new(0.0, -20.0), 0.5, 5.0);
+
+        assert!((steered - Vec2::new(0.0, -5.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn moving_is_limited() {
+        let moved = Vec2::new(10.0, 0.0).moved_towards(&Vec2::new(0.0, 0.0), 4.0);
+
+        assert!((moved - Vec2::new(6.0, 0.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn moving_stops_at_target() {
+        let moved = Vec2::new(1.0, 1.0).moved_towards(&Vec2::new(2.0, 1.0), 4.0);
+
+        assert_eq!(moved, Vec2::new(2.0, 1.0));
+    }
 }
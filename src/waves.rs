@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use rand::random;
+
+use crate::{
+    app_state::AppState,
+    components::{Enemy, Player},
+    elites::{elite_chance, roll_affixes},
+    enemy_kinds::{EnemyKinds, EnemyKindsHandle},
+    spawning::{spawn_marker, SpawnMarker, SpawnPlacement},
+};
+
+// Constants
+const INITIAL_THREAT_BUDGET: u32 = 10;
+const THREAT_BUDGET_GROWTH: u32 = 6;
+const SPAWN_INTERVAL: f32 = 0.75;
+const FIRST_WAVE_DELAY: f32 = 2.0;
+const INTERMISSION_TIME: f32 = 5.0;
+
+// Events
+pub struct WaveStarted(pub u32);
+pub struct WaveCleared(pub u32);
+
+// Resources
+enum WavePhase {
+    /// Waiting for the next wave to start
+    Intermission,
+    /// Spending the wave's threat budget on enemies
+    Spawning,
+    /// Everything has been spawned, waiting for the player to clean up
+    Fighting,
+}
+
+struct WaveDirector {
+    wave: u32,
+    phase: WavePhase,
+    threat_budget: u32,
+    spawn_timer: Timer,
+    intermission_timer: Timer,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self {
+            wave: 0,
+            phase: WavePhase::Intermission,
+            threat_budget: 0,
+            spawn_timer: Timer::from_seconds(SPAWN_INTERVAL, true),
+            intermission_timer: Timer::from_seconds(FIRST_WAVE_DELAY, false),
+        }
+    }
+}
+
+// Plugin
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<WaveStarted>()
+            .add_event::<WaveCleared>()
+            .add_system_set(SystemSet::on_update(AppState::Playing).with_system(wave_director))
+            .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(reset_wave_director))
+            .insert_resource(WaveDirector::default());
+    }
+}
+
+// Systems
+fn reset_wave_director(mut wave_director: ResMut<WaveDirector>) {
+    *wave_director = WaveDirector::default();
+}
+
+fn wave_director(
+    mut commands: Commands,
+    time: Res<Time>,
+    enemy_kinds: Res<Assets<EnemyKinds>>,
+    enemy_kinds_handle: Res<EnemyKindsHandle>,
+    mut wave_director: ResMut<WaveDirector>,
+    mut wave_started: EventWriter<WaveStarted>,
+    mut wave_cleared: EventWriter<WaveCleared>,
+    spawn_placement: SpawnPlacement,
+    player_transform: Query<&Transform, With<Player>>,
+    enemies: Query<Entity, Or<(With<Enemy>, With<SpawnMarker>)>>,
+) {
+    match wave_director.phase {
+        WavePhase::Intermission => {
+            wave_director.intermission_timer.tick(time.delta());
+
+            if wave_director.intermission_timer.finished() {
+                wave_director.wave += 1;
+                wave_director.threat_budget =
+                    INITIAL_THREAT_BUDGET + THREAT_BUDGET_GROWTH * (wave_director.wave - 1);
+                wave_director.phase = WavePhase::Spawning;
+                wave_director.spawn_timer.reset();
+
+                wave_started.send(WaveStarted(wave_director.wave));
+            }
+        }
+        WavePhase::Spawning => {
+            wave_director.spawn_timer.tick(time.delta());
+
+            if !wave_director.spawn_timer.just_finished() {
+                return;
+            }
+
+            // Kinds are loaded asynchronously, so there might be nothing to spawn yet
+            let enemy_kinds = match enemy_kinds.get(&**enemy_kinds_handle) {
+                Some(enemy_kinds) => enemy_kinds,
+                None => return,
+            };
+
+            // The wave is fully spawned once nothing fits into the remaining budget
+            let enemy_kind = match enemy_kinds.choose(wave_director.threat_budget) {
+                Some(enemy_kind) => enemy_kind,
+                None => {
+                    wave_director.phase = WavePhase::Fighting;
+                    return;
+                }
+            };
+
+            // Try again next tick if there is no room around the player right now
+            let player_position = player_transform.single().translation.truncate();
+            let enemy_position =
+                match spawn_placement.find(player_position, Vec2::splat(enemy_kind.size)) {
+                    Some(enemy_position) => enemy_position,
+                    None => return,
+                };
+
+            wave_director.threat_budget -= enemy_kind.threat;
+
+            if random::<f32>() < elite_chance(wave_director.wave) {
+                let mut elite_kind = enemy_kind.clone();
+                elite_kind
+                    .affixes
+                    .extend(roll_affixes(&mut rand::thread_rng()));
+
+                spawn_marker(&mut commands, &elite_kind, enemy_position);
+            } else {
+                spawn_marker(&mut commands, enemy_kind, enemy_position);
+            }
+        }
+        WavePhase::Fighting => {
+            // Enemies that are still announced by a marker keep the wave going too
+            if enemies.is_empty() {
+                wave_director.phase = WavePhase::Intermission;
+                wave_director.intermission_timer = Timer::from_seconds(INTERMISSION_TIME, false);
+
+                wave_cleared.send(WaveCleared(wave_director.wave));
+            }
+        }
+    }
+}
@@ -3,30 +3,71 @@ use std::f32::consts::FRAC_PI_6;
 use bevy::prelude::*;
 
 use crate::{
+    actions::Action,
+    app_state::{despawn_all, AppState},
     components::{
-        CurrentAmmo, Damage, Enemy, FireDelayTimer, Health, MaximumAmmo, Player, ReloadTimer,
-        Reloading, Weapon,
+        CurrentAmmo, Damage, Enemy, FireDelayTimer, Health, Knockback, KnockbackVelocity, Mass,
+        MaximumAmmo, Player, ReloadTimer, Reloading, RollCooldown, StunTimer, Stunned, Weapon,
     },
-    mouse::MousePosition,
+    gamepad::{update_aim, AimDirection},
+    player::firing_blocked,
+    spatial_grid::{update_spatial_grid, SpatialGrid},
+    status_effects::{OnHitEffect, StatusEffect, StatusEffects},
     util::VectorMath,
 };
 
 // Constants
-// (name, damage, RANGE, ammo, delay, reload)
-const WEAPONS: [(&str, i32, u32, f32, f32); 5] = [
-    ("Pistols", 10_i32, 30_u32, 0.3_f32, 2.0_f32),
+// (name, damage, RANGE, ammo, delay, reload, knockback, on hit)
+const WEAPONS: [(&str, i32, u32, f32, f32, f32, Option<StatusEffect>); 5] = [
+    ("Pistols", 10_i32, 30_u32, 0.3_f32, 2.0_f32, 150.0_f32, None),
     // Reload time is meant per pellet
-    ("Shotgun", 30_i32, 7_u32, 1.0_f32, 0.75_f32),
-    ("AssaultRifle", 15_i32, 30_u32, 0.1_f32, 1.5_f32),
-    ("RocketLauncher", 50_i32, 1_u32, 1.5_f32, 2.5_f32),
+    (
+        "Shotgun",
+        30_i32,
+        7_u32,
+        1.0_f32,
+        0.75_f32,
+        500.0_f32,
+        Some(StatusEffect::Slow),
+    ),
+    (
+        "AssaultRifle",
+        15_i32,
+        30_u32,
+        0.1_f32,
+        1.5_f32,
+        80.0_f32,
+        Some(StatusEffect::Poison),
+    ),
+    (
+        "RocketLauncher",
+        50_i32,
+        1_u32,
+        1.5_f32,
+        2.5_f32,
+        800.0_f32,
+        Some(StatusEffect::Burning),
+    ),
     // TODO: Laser has special ammo system
-    ("Laser", 10_i32, 30_u32, 0.1_f32, 1.5_f32),
+    (
+        "Laser",
+        10_i32,
+        30_u32,
+        0.1_f32,
+        1.5_f32,
+        0.0_f32,
+        Some(StatusEffect::Freeze),
+    ),
 ];
 
 // Resources
 #[derive(Default, Deref, DerefMut)]
 pub struct SelectedWeapon(Option<Entity>);
 
+/// Weapon fired by the secondary trigger, i.e. the one selected before the current one.
+#[derive(Default, Deref, DerefMut)]
+pub struct SecondaryWeapon(Option<Entity>);
+
 #[derive(Default, Deref, DerefMut)]
 struct Weapons(Vec<Entity>);
 
@@ -40,6 +81,8 @@ struct WeaponBundle {
     fire_delay_timer: FireDelayTimer,
     reload_timer: ReloadTimer,
     reloading: Reloading,
+    knockback: Knockback,
+    on_hit_effect: OnHitEffect,
     _weapon: Weapon,
 }
 
@@ -48,13 +91,19 @@ pub struct WeaponPlugin;
 
 impl Plugin for WeaponPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(spawn_weapons)
-            .add_system(select_weapon)
-            .add_system(shoot_weapon)
-            .add_system(reload_weapon.after(shoot_weapon))
+        app.add_system_set(SystemSet::on_enter(AppState::Playing).with_system(spawn_weapons))
+            .add_system_set(
+                SystemSet::on_update(AppState::Playing)
+                    .with_system(select_weapon)
+                    .with_system(shoot_weapon.after(update_spatial_grid).after(update_aim))
+                    .with_system(reload_weapon.after(shoot_weapon)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::GameOver).with_system(despawn_all::<Weapon>),
+            )
             .insert_resource(SelectedWeapon::default())
-            .insert_resource(Weapons::default())
-            .insert_resource(MousePosition::default());
+            .insert_resource(SecondaryWeapon::default())
+            .insert_resource(Weapons::default());
     }
 }
 
@@ -63,9 +112,13 @@ fn spawn_weapons(
     mut commands: Commands,
     mut weapons: ResMut<Weapons>,
     mut selected_weapon: ResMut<SelectedWeapon>,
+    mut secondary_weapon: ResMut<SecondaryWeapon>,
 ) {
+    // Left over from the previous run, whose weapons are gone by now
+    weapons.clear();
+
     // Add all weapons to weapon resource
-    for (name, damage, ammo, fire_delay, reload_time) in WEAPONS {
+    for (name, damage, ammo, fire_delay, reload_time, knockback, on_hit_effect) in WEAPONS {
         weapons.push(
             commands
                 .spawn_bundle(WeaponBundle {
@@ -76,6 +129,8 @@ fn spawn_weapons(
                     fire_delay_timer: FireDelayTimer(Timer::from_seconds(fire_delay, false)),
                     reload_timer: ReloadTimer(Timer::from_seconds(reload_time, false)),
                     reloading: Reloading(false),
+                    knockback: Knockback(knockback),
+                    on_hit_effect: OnHitEffect(on_hit_effect),
                     _weapon: Weapon,
                 })
                 .id(),
@@ -83,57 +138,105 @@ fn spawn_weapons(
 
         **selected_weapon = Some(weapons[0]);
     }
+
+    **secondary_weapon = weapons.get(1).copied();
 }
 
 // TODO: Add fire mode
 pub fn shoot_weapon(
-    mut commands: Commands,
-    mouse_buttons: Res<Input<MouseButton>>,
+    actions: Res<Input<Action>>,
     time: Res<Time>,
     selected_weapon: Res<SelectedWeapon>,
-    mouse_position: Res<MousePosition>,
+    secondary_weapon: Res<SecondaryWeapon>,
+    aim_direction: Res<AimDirection>,
+    spatial_grid: Res<SpatialGrid>,
     mut weapon_query: Query<(
         &mut CurrentAmmo,
         &mut FireDelayTimer,
         &mut Reloading,
         &mut ReloadTimer,
         &Damage,
+        &Knockback,
+        &OnHitEffect,
     )>,
-    player_transform: Query<&Transform, With<Player>>,
-    mut enemy_query: Query<(Entity, &Transform, &mut Health), (With<Enemy>, Without<Player>)>,
+    player_query: Query<(&Transform, &RollCooldown), With<Player>>,
+    mut enemy_query: Query<
+        (
+            &mut Health,
+            &Mass,
+            &mut KnockbackVelocity,
+            Option<(&mut Stunned, &mut StunTimer)>,
+            &mut StatusEffects,
+        ),
+        (With<Enemy>, Without<Player>),
+    >,
 ) {
-    if let Some(weapon_ent) = **selected_weapon {
+    let triggers = [
+        (Action::Fire, **selected_weapon),
+        (Action::SecondaryFire, **secondary_weapon),
+    ];
+
+    for (action, weapon_ent) in triggers {
+        let weapon_ent = match weapon_ent {
+            Some(weapon_ent) => weapon_ent,
+            None => continue,
+        };
+
         if let Ok((
             mut current_ammo,
             mut fire_delay_timer,
             mut reloading,
             mut reload_timer,
             weapon_damage,
+            weapon_knockback,
+            weapon_on_hit_effect,
         )) = weapon_query.get_mut(weapon_ent)
         {
             fire_delay_timer.tick(time.delta());
 
-            if mouse_buttons.just_pressed(MouseButton::Left)
+            let (player_transform, roll_cooldown) = player_query.single();
+
+            if actions.just_pressed(action)
                 && fire_delay_timer.finished()
                 && !**reloading
+                && !firing_blocked(roll_cooldown)
             {
                 **current_ammo -= 1;
                 fire_delay_timer.reset();
 
                 // Calculate damage
-                let player_position = player_transform.single().translation.truncate();
-                let scaled_target_vector = player_position.scaled_vector_to(&mouse_position, 500.0);
+                let player_position = player_transform.translation.truncate();
+                let scaled_target_vector = **aim_direction * 500.0;
                 let b = player_position + scaled_target_vector.rotated_by(FRAC_PI_6);
                 let c = player_position + scaled_target_vector.rotated_by(-FRAC_PI_6);
 
-                for (enemy_ent, enemy_transform, mut enemy_health) in enemy_query.iter_mut() {
-                    let enemy_position = enemy_transform.translation.truncate();
+                for entry in spatial_grid.query_triangle(player_position, b, c) {
+                    if let Ok((
+                        mut enemy_health,
+                        enemy_mass,
+                        mut knockback_velocity,
+                        stun,
+                        mut status_effects,
+                    )) = enemy_query.get_mut(entry.entity)
+                    {
+                        **enemy_health -= **weapon_damage;
+
+                        // Dead enemies are cleaned up by `enemy_deaths`
+                        if **enemy_health > 0 {
+                            // Push the enemy away from the player, less so the heavier it is
+                            **knockback_velocity += player_position.scaled_vector_to(
+                                &entry.position,
+                                **weapon_knockback / **enemy_mass,
+                            );
+
+                            if let Some((mut stunned, mut stun_timer)) = stun {
+                                **stunned = true;
+                                stun_timer.reset();
+                            }
 
-                    if enemy_position.is_in_triangle(&player_position, &b, &c) {
-                        if **weapon_damage >= **enemy_health {
-                            commands.entity(enemy_ent).despawn();
-                        } else {
-                            **enemy_health -= **weapon_damage;
+                            if let Some(effect) = **weapon_on_hit_effect {
+                                status_effects.apply(effect);
+                            }
                         }
                     }
                 }
@@ -148,10 +251,13 @@ pub fn shoot_weapon(
     }
 }
 
+/// Reloads the selected weapon on key press. The secondary weapon finishes its automatic reload
+/// meanwhile, so it is ready again for its own trigger.
 fn reload_weapon(
-    keys: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
     time: Res<Time>,
     selected_weapon: Res<SelectedWeapon>,
+    secondary_weapon: Res<SecondaryWeapon>,
     mut weapon_ammo: Query<(
         &MaximumAmmo,
         &mut CurrentAmmo,
@@ -159,7 +265,14 @@ fn reload_weapon(
         &mut ReloadTimer,
     )>,
 ) {
-    if let Some(weapon_ent) = **selected_weapon {
+    let weapons = [(**selected_weapon, true), (**secondary_weapon, false)];
+
+    for (weapon_ent, selected) in weapons {
+        let weapon_ent = match weapon_ent {
+            Some(weapon_ent) => weapon_ent,
+            None => continue,
+        };
+
         if let Ok((maximum_ammo, mut current_ammo, mut reloading, mut reload_timer)) =
             weapon_ammo.get_mut(weapon_ent)
         {
@@ -170,7 +283,7 @@ fn reload_weapon(
                     **current_ammo = **maximum_ammo;
                     **reloading = false;
                 }
-            } else if keys.pressed(KeyCode::R) {
+            } else if selected && actions.pressed(Action::Reload) {
                 **reloading = true;
                 reload_timer.reset();
             }
@@ -178,24 +291,39 @@ fn reload_weapon(
     }
 }
 
+/// Switches the selected weapon, handing the previous one over to the secondary trigger.
 fn select_weapon(
-    keys: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
     weapons: Res<Weapons>,
     mut selected_weapon: ResMut<SelectedWeapon>,
+    mut secondary_weapon: ResMut<SecondaryWeapon>,
 ) {
-    if keys.pressed(KeyCode::Key1) {
-        **selected_weapon = Some(weapons[0]);
-    }
-    if keys.pressed(KeyCode::Key2) {
-        **selected_weapon = Some(weapons[1]);
-    }
-    if keys.pressed(KeyCode::Key3) {
-        **selected_weapon = Some(weapons[2]);
+    let previous_weapon = **selected_weapon;
+
+    // Cycle through the weapons, wrapping around at either end
+    let cycle = if actions.just_pressed(Action::NextWeapon) {
+        1
+    } else if actions.just_pressed(Action::PreviousWeapon) {
+        weapons.len() - 1
+    } else {
+        0
+    };
+
+    if cycle != 0 {
+        let selected = weapons
+            .iter()
+            .position(|weapon_ent| Some(*weapon_ent) == **selected_weapon)
+            .unwrap_or(0);
+        **selected_weapon = Some(weapons[(selected + cycle) % weapons.len()]);
     }
-    if keys.pressed(KeyCode::Key4) {
-        **selected_weapon = Some(weapons[3]);
+
+    for (index, weapon_ent) in weapons.iter().enumerate() {
+        if actions.pressed(Action::SelectWeapon(index + 1)) {
+            **selected_weapon = Some(*weapon_ent);
+        }
     }
-    if keys.pressed(KeyCode::Key5) {
-        **selected_weapon = Some(weapons[4]);
+
+    if **selected_weapon != previous_weapon {
+        **secondary_weapon = previous_weapon;
     }
 }